@@ -0,0 +1,240 @@
+use crate::chess::bitboard::Squares;
+use crate::chess::{Color, Game, Piece};
+
+// Piece-square tables from whites perspective, written with rank 8 at the top
+// so they read like a board. Values are added to the material value of a piece.
+
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     80,  80,  80,  80,  80,  80,  80,  80,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     30,  30,  30,  30,  30,  30,  30,  30,
+     20,  20,  20,  20,  20,  20,  20,  20,
+     10,  10,  10,  10,  10,  10,  10,  10,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// Phase of a game with all non-pawn material still on the board.
+const MAX_PHASE: i32 = 24;
+
+/// Material value in the middlegame and endgame, pawns and rooks gain value once the
+/// board empties while the minor pieces lose some.
+pub fn piece_value(piece: Piece) -> (i32, i32) {
+    let endgame = match piece {
+        Piece::Pawn => 120,
+        Piece::Knight => 300,
+        Piece::Bishop => 320,
+        Piece::Rook => 520,
+        Piece::Queen => 900,
+        Piece::King => 0,
+    };
+    (piece.value(), endgame)
+}
+
+/// How much a piece contributes to the game phase, used to taper between
+/// middlegame and endgame scores.
+fn phase_weight(piece: Piece) -> i32 {
+    match piece {
+        Piece::Knight | Piece::Bishop => 1,
+        Piece::Rook => 2,
+        Piece::Queen => 4,
+        Piece::Pawn | Piece::King => 0,
+    }
+}
+
+fn piece_square(piece: Piece, index: usize) -> (i32, i32) {
+    match piece {
+        Piece::Pawn => (PAWN_MG[index], PAWN_EG[index]),
+        Piece::Knight => (KNIGHT[index], KNIGHT[index]),
+        Piece::Bishop => (BISHOP[index], BISHOP[index]),
+        Piece::Rook => (ROOK[index], ROOK[index]),
+        Piece::Queen => (QUEEN[index], QUEEN[index]),
+        Piece::King => (KING_MG[index], KING_EG[index]),
+    }
+}
+
+/// Scores `game` in centipawns from the perspective of the active color.
+pub fn evaluate(game: &Game) -> i32 {
+    let (mut mg, mut eg, mut phase) = (0, 0, 0);
+    for color in &[Color::White, Color::Black] {
+        let sign = if *color == game.get_active_color() {
+            1
+        } else {
+            -1
+        };
+        for piece in &Piece::ALL {
+            for square in Squares(game.get_bitboard(*color, *piece)) {
+                // tables are written from whites perspective with rank 8 first,
+                // flipping the rank of a white piece gives its index
+                let index = match color {
+                    Color::White => square ^ 56,
+                    Color::Black => square,
+                };
+                let (value_mg, value_eg) = piece_value(*piece);
+                let (square_mg, square_eg) = piece_square(*piece, index);
+                mg += sign * (value_mg + square_mg);
+                eg += sign * (value_eg + square_eg);
+                phase += phase_weight(*piece);
+            }
+        }
+    }
+    let phase = phase.min(MAX_PHASE);
+    (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, KING_EG};
+    use crate::chess::Game;
+
+    fn other_color(color: &str) -> &str {
+        match color {
+            "w" => "b",
+            _ => "w",
+        }
+    }
+
+    /// Flips the board vertically and swaps the colors of all pieces and the side to move.
+    fn mirror(fen: &str) -> String {
+        let fields = fen.split(' ').collect::<Vec<_>>();
+        let board = fields[0]
+            .split('/')
+            .rev()
+            .collect::<Vec<_>>()
+            .join("/")
+            .chars()
+            .map(|c| match c.is_uppercase() {
+                true => c.to_ascii_lowercase(),
+                false => c.to_ascii_uppercase(),
+            })
+            .collect::<String>();
+        format!("{} {} - - 0 1", board, other_color(fields[1]))
+    }
+
+    fn eval(fen: &str) -> i32 {
+        evaluate(&Game::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn start_position_is_equal() {
+        assert_eq!(
+            eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            0
+        );
+        assert_eq!(
+            eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"),
+            0
+        );
+    }
+
+    #[test]
+    fn mirrored_positions_score_the_same() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+            "4k3/8/8/8/8/8/1P6/4K1N1 b - - 0 1",
+        ];
+        for fen in fens.iter() {
+            let score = eval(fen);
+            assert_ne!(score, 0, "{}", fen);
+            assert_eq!(score, eval(&mirror(fen)), "{}", fen);
+            // the same position with the other side to move scores the opposite
+            let fields = fen.split(' ').collect::<Vec<_>>();
+            let other_side = format!("{} {} - - 0 1", fields[0], other_color(fields[1]));
+            assert_eq!(eval(&other_side), -score, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn bare_kings_use_endgame_tables() {
+        // white king on a1, black king on e8, indexed with rank 8 first
+        assert_eq!(
+            eval("4k3/8/8/8/8/8/8/K7 w - - 0 1"),
+            KING_EG[56] - KING_EG[60]
+        );
+        assert_eq!(
+            eval("4k3/8/8/8/8/8/8/K7 b - - 0 1"),
+            KING_EG[60] - KING_EG[56]
+        );
+    }
+}
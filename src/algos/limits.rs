@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use crate::chess::Color;
+
+/// Depth used when nothing else limits the search.
+pub const DEFAULT_DEPTH: usize = 3;
+/// Depth searched to when only time or nodes limit the search.
+pub const MAX_DEPTH: usize = 64;
+/// Moves the remaining time is split over if the frontend doesn't send `movestogo`.
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Time kept back from the clock for the communication with the frontend.
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// Limits of a search, named like the parameters of the UCI `go` command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: usize) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    /// Parses pairs of limit names and values, e.g. `wtime 60000 btime 60000 movestogo 20`.
+    /// Times are in milliseconds.
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        let mut limits = Self::default();
+        if !args.len().is_multiple_of(2) {
+            return Err(format!("Limit {:?} has no value", args[args.len() - 1]));
+        }
+        for pair in args.chunks(2) {
+            let (name, value) = (pair[0], pair[1]);
+            let number = value
+                .parse::<u64>()
+                .map_err(|e| format!("Value of limit {:?} is not a number: {}", name, e))?;
+            let millis = Some(Duration::from_millis(number));
+            match name {
+                "depth" => limits.depth = Some(number as usize),
+                "nodes" => limits.nodes = Some(number),
+                "movetime" => limits.movetime = millis,
+                "wtime" => limits.wtime = millis,
+                "btime" => limits.btime = millis,
+                "winc" => limits.winc = millis,
+                "binc" => limits.binc = millis,
+                "movestogo" => limits.movestogo = Some(number as u32),
+                _ => return Err(format!("Unknown limit: {:?}", name)),
+            }
+        }
+        Ok(limits)
+    }
+
+    /// Without a depth the search goes on until the nodes or the time of `color` run out.
+    /// If none of those are given, e.g. only the clock of the other color, it would never
+    /// stop, so the default depth is used.
+    pub fn max_depth(&self, color: Color) -> usize {
+        match self.depth {
+            Some(depth) => depth.max(1),
+            None if self.nodes.is_some()
+                || self.movetime.is_some()
+                || self.clock(color).0.is_some() =>
+            {
+                MAX_DEPTH
+            }
+            None => DEFAULT_DEPTH,
+        }
+    }
+
+    /// Remaining time and increment of the color.
+    fn clock(&self, color: Color) -> (Option<Duration>, Option<Duration>) {
+        match color {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        }
+    }
+}
+
+/// Decides how long a search may take.
+/// The soft limit is checked between the iterations of iterative deepening, a new iteration
+/// is not started after it. The hard limit aborts the running iteration.
+pub struct TimeManager {
+    start: Instant,
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, color: Color) -> Self {
+        let (soft, hard) = match (limits.movetime, limits.clock(color)) {
+            (Some(movetime), _) => (Some(movetime), Some(movetime)),
+            (None, (Some(time), inc)) => {
+                let available = time.saturating_sub(MOVE_OVERHEAD);
+                let moves_to_go = limits.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+                let budget = available / moves_to_go + inc.unwrap_or_default() * 3 / 4;
+                let hard = (budget * 3).min(available / 2);
+                // the next iteration usually takes longer than all previous ones together,
+                // so it is only started in the first half of the budget
+                (Some((budget / 2).min(hard)), Some(hard))
+            }
+            (None, (None, _)) => (None, None),
+        };
+        Self {
+            start: Instant::now(),
+            soft,
+            hard,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub fn can_start_iteration(&self) -> bool {
+        self.soft.is_none_or(|soft| self.elapsed() < soft)
+    }
+
+    pub fn out_of_time(&self) -> bool {
+        self.hard.is_some_and(|hard| self.elapsed() >= hard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{SearchLimits, TimeManager, DEFAULT_DEPTH, MAX_DEPTH};
+    use crate::chess::Color;
+
+    #[test]
+    fn parse_limits() {
+        let limits =
+            SearchLimits::parse(&["wtime", "60000", "btime", "30000", "movestogo", "20"]).unwrap();
+        assert_eq!(limits.wtime, Some(Duration::from_secs(60)));
+        assert_eq!(limits.btime, Some(Duration::from_secs(30)));
+        assert_eq!(limits.movestogo, Some(20));
+        assert_eq!(limits.max_depth(Color::White), MAX_DEPTH);
+        let max_depth = |args: &[&str]| SearchLimits::parse(args).unwrap().max_depth(Color::White);
+        assert_eq!(max_depth(&[]), DEFAULT_DEPTH);
+        assert_eq!(max_depth(&["depth", "5"]), 5);
+        assert_eq!(max_depth(&["nodes", "5000"]), MAX_DEPTH);
+        assert!(SearchLimits::parse(&["depth"]).is_err());
+        assert!(SearchLimits::parse(&["depth", "x"]).is_err());
+        assert!(SearchLimits::parse(&["ponder", "1"]).is_err());
+    }
+
+    #[test]
+    fn time_budget_stays_within_the_clock() {
+        let limits = SearchLimits::parse(&["wtime", "1000", "btime", "600000"]).unwrap();
+        let white = TimeManager::new(&limits, Color::White);
+        let black = TimeManager::new(&limits, Color::Black);
+        assert!(white.hard.unwrap() <= Duration::from_millis(500));
+        assert!(white.soft.unwrap() <= white.hard.unwrap());
+        assert!(black.hard.unwrap() > white.hard.unwrap());
+        let movetime = SearchLimits::parse(&["movetime", "200"]).unwrap();
+        let timer = TimeManager::new(&movetime, Color::White);
+        assert_eq!(timer.hard, Some(Duration::from_millis(200)));
+        assert!(!TimeManager::new(&SearchLimits::default(), Color::White).out_of_time());
+        // limits that only apply to the other color don't limit the search, it stops at
+        // the default depth instead of running forever
+        for args in [
+            &["btime", "1000"][..],
+            &["winc", "1000"],
+            &["binc", "1000"],
+            &["movestogo", "10"],
+        ]
+        .iter()
+        {
+            let limits = SearchLimits::parse(args).unwrap();
+            let timer = TimeManager::new(&limits, Color::White);
+            assert_eq!((timer.soft, timer.hard), (None, None), "{:?}", args);
+            assert_eq!(limits.max_depth(Color::White), DEFAULT_DEPTH, "{:?}", args);
+        }
+        let limits = SearchLimits::parse(&["btime", "1000"]).unwrap();
+        assert!(TimeManager::new(&limits, Color::Black).hard.is_some());
+        assert_eq!(limits.max_depth(Color::Black), MAX_DEPTH);
+    }
+}
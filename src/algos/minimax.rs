@@ -1,5 +1,426 @@
+use std::time::Duration;
+
+use super::eval;
+use super::limits::{SearchLimits, TimeManager};
+use super::picker::{is_quiet, Heuristics, MovePicker};
+use super::tt::{self, Bound, TranspositionTable};
 use crate::chess::{Game, Move};
 
-pub fn minimax_move(game: &Game) -> Move {
-    todo!()
+/// Score of being checkmated right now. Mates further in the future score
+/// closer to zero by one per ply so that faster mates are preferred.
+pub const MATE_SCORE: i32 = 1_000_000;
+pub const MAX_MATE_PLY: i32 = 1000;
+/// Safety margin of delta pruning for positional gains of a capture.
+pub const DELTA_MARGIN: i32 = 200;
+
+/// State shared by all nodes of one search.
+pub struct Search<'a> {
+    pub tt: &'a mut TranspositionTable,
+    pub timer: TimeManager,
+    pub heuristics: Heuristics,
+    pub nodes: u64,
+    max_nodes: Option<u64>,
+    /// set when a limit is hit, the results of the running iteration can't be trusted then
+    pub stopped: bool,
+    completed_depth: usize,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(tt: &'a mut TranspositionTable, limits: &SearchLimits, game: &Game) -> Self {
+        Self {
+            tt,
+            timer: TimeManager::new(limits, game.get_active_color()),
+            heuristics: Heuristics::new(),
+            nodes: 0,
+            max_nodes: limits.nodes,
+            stopped: false,
+            completed_depth: 0,
+        }
+    }
+
+    /// Counts a node and checks the limits. The first iteration always completes so that
+    /// there is a move to play.
+    fn should_stop(&mut self) -> bool {
+        self.nodes += 1;
+        if self.completed_depth > 0
+            && (self.max_nodes.is_some_and(|max| self.nodes >= max)
+                || self.nodes.is_multiple_of(1024) && self.timer.out_of_time())
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    /// Orders the moves with the hash move of the position and the cutoffs so far.
+    fn picker(&self, game: &Game, moves: Vec<Move>, ply: usize) -> MovePicker {
+        let entry = self.tt.probe(game.get_hash());
+        let hash_move = entry.and_then(|e| e.best_move.as_ref());
+        MovePicker::new(game, moves, hash_move, &self.heuristics, ply)
+    }
+}
+
+/// One of the best moves of the root position, its score and the moves both sides play
+/// after it (the principal variation).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    pub score: i32,
+    pub moves: Vec<Move>,
+}
+
+/// Reported after every completed iteration, the best line first.
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: usize,
+    pub nodes: u64,
+    pub time: Duration,
+    pub lines: Vec<PvLine>,
+}
+
+/// Searches the `multipv` best moves and calls `report` after every iteration.
+pub fn minimax_move(
+    game: &Game,
+    limits: &SearchLimits,
+    multipv: usize,
+    tt: &mut TranspositionTable,
+    report: &mut dyn FnMut(&SearchInfo),
+) -> Result<(Move, i32), String> {
+    let mut game = game.clone();
+    if game.get_all_possible_moves().is_empty() {
+        return Err("there are no possible moves".to_string());
+    }
+    tt.new_search();
+    let mut search = Search::new(tt, limits, &game);
+    let line = iterative_deepening(&mut game, limits, multipv, &mut search, report)
+        .ok_or_else(|| "there are no possible moves".to_string())?;
+    Ok((line.moves[0].clone(), line.score))
+}
+
+/// Searches `game` to a fixed depth with an empty table and returns the number of nodes.
+/// The better the moves are ordered the fewer nodes are needed.
+pub fn count_nodes(game: &Game, depth: usize) -> u64 {
+    let mut tt = TranspositionTable::new(tt::DEFAULT_SIZE_MB).unwrap();
+    let limits = SearchLimits::depth(depth);
+    let mut search = Search::new(&mut tt, &limits, game);
+    iterative_deepening(&mut game.clone(), &limits, 1, &mut search, &mut |_| {});
+    search.nodes
+}
+
+/// Searches one ply deeper in every iteration until a limit is hit. The hash move of the
+/// previous iteration is searched first, so the table is well filled for the next one.
+/// Returns the best line of the last completed iteration.
+fn iterative_deepening(
+    game: &mut Game,
+    limits: &SearchLimits,
+    multipv: usize,
+    search: &mut Search,
+    report: &mut dyn FnMut(&SearchInfo),
+) -> Option<PvLine> {
+    let mut best = None;
+    for depth in 1..=limits.max_depth(game.get_active_color()) {
+        let lines = match search_root(game, depth, multipv.max(1), search) {
+            Some(lines) => lines,
+            None => break,
+        };
+        search.completed_depth = depth;
+        best = lines.first().cloned();
+        report(&SearchInfo {
+            depth,
+            nodes: search.nodes,
+            time: search.timer.elapsed(),
+            lines,
+        });
+        if !search.timer.can_start_iteration() {
+            break;
+        }
+    }
+    best
+}
+
+/// Finds the best line, then the best line without the first move of the previous lines
+/// and so on until there are `multipv` lines.
+/// Returns `None` if the search was stopped before all moves were searched.
+fn search_root(
+    game: &mut Game,
+    depth: usize,
+    multipv: usize,
+    search: &mut Search,
+) -> Option<Vec<PvLine>> {
+    let hash = game.get_hash();
+    let mut moves = game.get_all_possible_moves();
+    let mut lines: Vec<PvLine> = vec![];
+    while lines.len() < multipv && !moves.is_empty() {
+        let mut alpha = -MATE_SCORE - 1;
+        let beta = MATE_SCORE + 1;
+        let mut best: Option<PvLine> = None;
+        for m in search.picker(game, moves.clone(), 0) {
+            let mut pv = vec![];
+            let undo = game.make_move_unchecked(&m);
+            let score = -negamax(game, depth - 1, 1, -beta, -alpha, search, &mut pv);
+            game.unmake_move(&m, &undo);
+            if search.stopped {
+                return None;
+            }
+            if best.is_none() || score > alpha {
+                alpha = score;
+                pv.insert(0, m);
+                best = Some(PvLine { score, moves: pv });
+            }
+        }
+        let mut line = best?;
+        extend_pv(game, &mut line.moves, search.tt, depth);
+        moves.retain(|m| *m != line.moves[0]);
+        lines.push(line);
+    }
+    let best = &lines[0];
+    search.tt.store(
+        hash,
+        depth,
+        Bound::Exact,
+        best.score,
+        Some(best.moves[0].clone()),
+        0,
+    );
+    Some(lines)
+}
+
+/// Nodes that return a score from the table have no principal variation, the line is
+/// continued with the best moves stored in the table up to `depth` moves.
+fn extend_pv(game: &Game, pv: &mut Vec<Move>, tt: &TranspositionTable, depth: usize) {
+    let mut game = game.clone();
+    for m in pv.iter() {
+        game.make_move_unchecked(m);
+    }
+    while pv.len() < depth && game.repetition_count() < 2 {
+        let m = match tt.probe(game.get_hash()).and_then(|e| e.best_move.clone()) {
+            Some(m) if game.get_all_possible_moves().contains(&m) => m,
+            _ => break,
+        };
+        game.make_move_unchecked(&m);
+        pv.push(m);
+    }
+}
+
+/// Scores `game` from the perspective of the active color and fills `pv` with the best
+/// line found from here, unless the score is taken from the table or the search is quiet.
+pub fn negamax(
+    game: &mut Game,
+    depth: usize,
+    ply: usize,
+    mut alpha: i32,
+    beta: i32,
+    search: &mut Search,
+    pv: &mut Vec<Move>,
+) -> i32 {
+    if search.should_stop() {
+        return 0;
+    }
+    if game.repetition_count() >= 2 || game.is_insufficient_material() {
+        return 0;
+    }
+    if game.get_halfmove_clock() >= 100 {
+        // a mate with the hundredth half move ends the game before the draw can be claimed,
+        // otherwise the side that is worse off claims it
+        return match !game.has_legal_move() && game.is_king_attacked(game.get_active_color()) {
+            true => -MATE_SCORE + ply as i32,
+            false => 0,
+        };
+    }
+    let hash = game.get_hash();
+    if let Some(entry) = search.tt.probe(hash) {
+        if entry.depth >= depth {
+            let score = entry.score(ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return beta,
+                Bound::Upper if score <= alpha => return alpha,
+                _ => {}
+            }
+        }
+    }
+    if depth == 0 {
+        return quiescence(game, ply, alpha, beta, search);
+    }
+    let moves = game.get_all_possible_moves();
+    if moves.is_empty() {
+        return match game.is_king_attacked(game.get_active_color()) {
+            true => -MATE_SCORE + ply as i32,
+            false => 0,
+        };
+    }
+    let mut best_move = None;
+    let mut child_pv = vec![];
+    for m in search.picker(game, moves, ply) {
+        child_pv.clear();
+        let undo = game.make_move_unchecked(&m);
+        let score = -negamax(
+            game,
+            depth - 1,
+            ply + 1,
+            -beta,
+            -alpha,
+            search,
+            &mut child_pv,
+        );
+        game.unmake_move(&m, &undo);
+        if search.stopped {
+            return 0;
+        }
+        if score >= beta {
+            if is_quiet(game, &m) {
+                search.heuristics.record_cutoff(game, &m, depth, ply);
+            }
+            search
+                .tt
+                .store(hash, depth, Bound::Lower, beta, Some(m), ply);
+            return beta;
+        }
+        if score > alpha {
+            alpha = score;
+            pv.clear();
+            pv.push(m.clone());
+            pv.append(&mut child_pv);
+            best_move = Some(m);
+        }
+    }
+    let bound = match best_move {
+        Some(_) => Bound::Exact,
+        None => Bound::Upper,
+    };
+    search.tt.store(hash, depth, bound, alpha, best_move, ply);
+    alpha
+}
+
+/// Searches only captures and promotions until the position is quiet, so that positions
+/// in the middle of an exchange are not evaluated. The side to move may "stand pat" and
+/// take the static evaluation instead, unless it is in check and has to evade.
+fn quiescence(game: &mut Game, ply: usize, mut alpha: i32, beta: i32, search: &mut Search) -> i32 {
+    if search.should_stop() {
+        return 0;
+    }
+    let in_check = game.is_king_attacked(game.get_active_color());
+    let stand_pat = match in_check {
+        true => None,
+        false => Some(eval::evaluate(game)),
+    };
+    if let Some(stand_pat) = stand_pat {
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+    }
+    let moves = match in_check {
+        true => game.get_all_possible_moves(),
+        false => game.get_captures(),
+    };
+    if in_check && moves.is_empty() {
+        return -MATE_SCORE + ply as i32;
+    }
+    // without ordering the cutoffs come too late and the capture sequences explode
+    let moves = MovePicker::new(game, moves, None, &search.heuristics, ply);
+    for m in moves {
+        match stand_pat {
+            Some(stand_pat) if !matches!(m, Move::PawnPromotion(..)) => {
+                let gain = game.get_captured_piece(&m).map_or(0, |piece| piece.value());
+                // even winning the piece for free would not raise alpha
+                if stand_pat + gain + DELTA_MARGIN <= alpha {
+                    continue;
+                }
+                // standing pat is better than losing material in the exchange
+                if game.see(&m) < 0 {
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        let undo = game.make_move_unchecked(&m);
+        let score = -quiescence(game, ply + 1, -beta, -alpha, search);
+        game.unmake_move(&m, &undo);
+        if search.stopped {
+            return 0;
+        }
+        if score >= beta {
+            return beta;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+/// Formats a score like UCI does, either `cp <centipawns>` or `mate <moves>`.
+/// The number of moves is negative if the active color is getting mated.
+pub fn format_score(score: i32) -> String {
+    if score.abs() > MATE_SCORE - MAX_MATE_PLY {
+        let plies = MATE_SCORE - score.abs();
+        let moves = (plies + 1) / 2;
+        format!("mate {}", if score > 0 { moves } else { -moves })
+    } else {
+        format!("cp {}", score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minimax_move, SearchInfo, MATE_SCORE};
+    use crate::algos::limits::SearchLimits;
+    use crate::algos::tt::TranspositionTable;
+    use crate::chess::{Game, Move};
+
+    fn best_move(fen: &str, depth: usize) -> Move {
+        let game = Game::from_fen(fen).unwrap();
+        let mut tt = TranspositionTable::new(1).unwrap();
+        minimax_move(&game, &SearchLimits::depth(depth), 1, &mut tt, &mut |_| {})
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn quiescence_sees_recapture() {
+        // the pawn is defended by the king, taking it loses the queen
+        let m = best_move("4k3/3p4/8/8/8/8/8/3QK3 w - - 0 1", 1);
+        assert_ne!(m, Move::deserialize("b,d1-d7").unwrap());
+        // an undefended pawn is won
+        let m = best_move("4k3/8/8/8/8/8/8/3pK3 w - - 0 1", 1);
+        assert_eq!(m, Move::deserialize("b,e1-d1").unwrap());
+    }
+
+    #[test]
+    fn mate_with_the_hundredth_half_move() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+        let mut tt = TranspositionTable::new(1).unwrap();
+        let (m, score) =
+            minimax_move(&game, &SearchLimits::depth(2), 1, &mut tt, &mut |_| {}).unwrap();
+        assert_eq!(m, Move::deserialize("b,a1-a8").unwrap());
+        assert_eq!(score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn principal_variations() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        let mut tt = TranspositionTable::new(1).unwrap();
+        let mut infos: Vec<SearchInfo> = vec![];
+        let (m, score) = minimax_move(&game, &SearchLimits::depth(4), 3, &mut tt, &mut |info| {
+            infos.push(info.clone())
+        })
+        .unwrap();
+        assert_eq!(m, Move::deserialize("b,a1-a8").unwrap());
+        assert_eq!(score, MATE_SCORE - 1);
+        assert_eq!(
+            infos.iter().map(|i| i.depth).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        let lines = &infos[3].lines;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].moves, [m]);
+        for (i, line) in lines.iter().enumerate() {
+            // every line starts with a different move and is playable
+            assert!(lines[..i].iter().all(|l| l.moves[0] != line.moves[0]));
+            assert!(i == 0 || line.score <= lines[i - 1].score);
+            let mut game = game.clone();
+            for m in &line.moves {
+                assert!(game.get_all_possible_moves().contains(m), "{:?}", line);
+                game.make_move_unchecked(m);
+            }
+        }
+        assert_eq!(lines[1].moves.len(), 4);
+    }
 }
@@ -1,12 +1,30 @@
 use crate::chess::{Game, Move};
+use limits::SearchLimits;
+use minimax::SearchInfo;
+use tt::TranspositionTable;
 
+pub mod eval;
+pub mod limits;
 pub mod minimax;
+pub mod picker;
 pub mod random;
+pub mod tt;
 
-pub fn algo_move(name: &str, game: &Game) -> Result<Move, String> {
+/// Returns the chosen move and, if the algorithm computes one, its score.
+/// Algorithms that search deeper and deeper call `report` after every iteration with the
+/// `multipv` best lines.
+pub fn algo_move(
+    name: &str,
+    game: &Game,
+    limits: &SearchLimits,
+    multipv: usize,
+    tt: &mut TranspositionTable,
+    report: &mut dyn FnMut(&SearchInfo),
+) -> Result<(Move, Option<i32>), String> {
     match name {
-        "minimax" => Ok(minimax::minimax_move(game)),
-        "random" => Ok(random::random_move(game)),
+        "minimax" => minimax::minimax_move(game, limits, multipv, tt, report)
+            .map(|(m, score)| (m, Some(score))),
+        "random" => random::random_move(game).map(|m| (m, None)),
         _ => Err(format!("Unknown algo: {:?}", name)),
     }
 }
@@ -0,0 +1,158 @@
+use crate::chess::{Game, Move, Piece};
+
+/// Plies killer moves are kept for, deeper nodes don't get any.
+pub const MAX_PLY: usize = 128;
+
+const HASH_MOVE_SCORE: i32 = 1 << 30;
+const CAPTURE_SCORE: i32 = 1 << 29;
+const KILLER_SCORE: i32 = 1 << 28;
+const LOSING_CAPTURE_SCORE: i32 = 1 << 27;
+/// All history scores are halved once one of them reaches this, so they stay below the
+/// killers and old cutoffs count less than new ones.
+const HISTORY_LIMIT: i32 = 1 << 20;
+
+/// Quiet moves that caused beta cutoffs earlier in the search.
+pub struct Heuristics {
+    /// the last two different cutoff moves of every ply, siblings often have the same refutation
+    killers: Vec<[Option<Move>; 2]>,
+    /// indexed by color, source tile and target tile, higher for more and deeper cutoffs
+    history: Vec<i32>,
+}
+
+impl Heuristics {
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None, None]; MAX_PLY],
+            history: vec![0; 2 * 64 * 64],
+        }
+    }
+
+    /// Remembers a quiet move that caused a beta cutoff.
+    pub fn record_cutoff(&mut self, game: &Game, m: &Move, depth: usize, ply: usize) {
+        if let Some(killers) = self.killers.get_mut(ply) {
+            if killers[0].as_ref() != Some(m) {
+                killers[1] = killers[0].take();
+                killers[0] = Some(m.clone());
+            }
+        }
+        let index = history_index(game, m);
+        self.history[index] += (depth * depth) as i32;
+        if self.history[index] >= HISTORY_LIMIT {
+            for score in self.history.iter_mut() {
+                *score /= 2;
+            }
+        }
+    }
+
+    fn is_killer(&self, m: &Move, ply: usize) -> bool {
+        self.killers
+            .get(ply)
+            .is_some_and(|killers| killers.iter().any(|k| k.as_ref() == Some(m)))
+    }
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn history_index(game: &Game, m: &Move) -> usize {
+    game.get_active_color().index() * 64 * 64
+        + m.get_source_coord().index() * 64
+        + m.get_target_coord().index()
+}
+
+/// Captures and promotions change the material, everything else is quiet.
+pub fn is_quiet(game: &Game, m: &Move) -> bool {
+    !matches!(m, Move::PawnPromotion(..)) && game.get_captured_piece(m).is_none()
+}
+
+/// Most valuable victim first, the least valuable attacker first among equal victims.
+/// Promotions gain the value of the new piece.
+pub fn mvv_lva(game: &Game, m: &Move) -> i32 {
+    let value = |piece: Option<Piece>| piece.map_or(0, |p| p.value());
+    let promotion = match m {
+        Move::PawnPromotion(_, _, piece) => value(Some(*piece)),
+        _ => 0,
+    };
+    let victim = value(game.get_captured_piece(m));
+    let attacker = value(game.get_tile(&m.get_source_coord()).map(|t| t.1));
+    (victim + promotion) * 16 - attacker / 16
+}
+
+/// Hands out the moves of a position in the order they most likely cause a cutoff:
+/// the hash move, then captures and promotions that don't lose material by `mvv_lva`,
+/// then the killer moves, then the losing captures and then the other quiet moves by
+/// their history score.
+pub struct MovePicker {
+    moves: Vec<(Move, i32)>,
+}
+
+impl MovePicker {
+    pub fn new(
+        game: &Game,
+        moves: Vec<Move>,
+        hash_move: Option<&Move>,
+        heuristics: &Heuristics,
+        ply: usize,
+    ) -> Self {
+        let moves = moves
+            .into_iter()
+            .map(|m| {
+                let score = if Some(&m) == hash_move {
+                    HASH_MOVE_SCORE
+                } else if !is_quiet(game, &m) {
+                    match game.see(&m) >= 0 {
+                        true => CAPTURE_SCORE + mvv_lva(game, &m),
+                        false => LOSING_CAPTURE_SCORE + mvv_lva(game, &m),
+                    }
+                } else if heuristics.is_killer(&m, ply) {
+                    KILLER_SCORE
+                } else {
+                    heuristics.history[history_index(game, &m)]
+                };
+                (m, score)
+            })
+            .collect();
+        Self { moves }
+    }
+}
+
+impl Iterator for MovePicker {
+    type Item = Move;
+
+    /// Takes out the best remaining move instead of sorting all moves up front, after a
+    /// cutoff the rest is never looked at.
+    fn next(&mut self) -> Option<Move> {
+        let (best, _) = self
+            .moves
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, (_, score))| *score)?;
+        Some(self.moves.swap_remove(best).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Heuristics, MovePicker};
+    use crate::chess::{Game, Move};
+
+    #[test]
+    fn picker_order() {
+        let game = Game::from_fen("4k3/8/2r3q1/3P4/8/8/8/R3K1N1 w - - 0 1").unwrap();
+        let mv = |s| Move::deserialize(s).unwrap();
+        let mut heuristics = Heuristics::new();
+        heuristics.record_cutoff(&game, &mv("b,a1-a7"), 3, 0);
+        heuristics.record_cutoff(&game, &mv("b,g1-f3"), 1, 4);
+        let moves = game.get_all_possible_moves();
+        let hash_move = mv("b,e1-f1");
+        let order = MovePicker::new(&game, moves, Some(&hash_move), &heuristics, 0)
+            .map(|m| m.serialize())
+            .collect::<Vec<_>>();
+        // hash move, the pawn takes the most valuable piece, the killer of this ply and
+        // the quiet move with the best history
+        assert_eq!(order[..4], ["b,e1-f1", "b,d5-c6", "b,a1-a7", "b,g1-f3"]);
+    }
+}
@@ -2,9 +2,28 @@ use rand::Rng;
 
 use crate::chess::{Game, Move};
 
-pub fn random_move(game: &Game) -> Move {
+pub fn random_move(game: &Game) -> Result<Move, String> {
     let moves = game.get_all_possible_moves();
+    if moves.is_empty() {
+        return Err("there are no possible moves".to_string());
+    }
     let mut rng = rand::thread_rng();
     let index = rng.gen_range(0..moves.len());
-    return moves[index].clone();
-}
\ No newline at end of file
+    Ok(moves[index].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::random_move;
+    use crate::chess::Game;
+
+    #[test]
+    fn random_move_without_moves() {
+        let game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(random_move(&game).is_err());
+        let game = Game::from_fen("7k/8/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(game
+            .get_all_possible_moves()
+            .contains(&random_move(&game).unwrap()));
+    }
+}
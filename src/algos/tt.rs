@@ -0,0 +1,197 @@
+use super::minimax::{MATE_SCORE, MAX_MATE_PLY};
+use crate::chess::Move;
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+pub const MAX_SIZE_MB: usize = 4096;
+
+/// How the stored score relates to the real score of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower, // the search failed high, the real score is at least this
+    Upper, // the search failed low, the real score is at most this
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    key: u64,
+    pub best_move: Option<Move>,
+    score: i32,
+    pub depth: usize,
+    pub bound: Bound,
+    age: u8,
+}
+
+impl Entry {
+    /// Score of the entry as seen from a node `ply` half moves away from the root.
+    pub fn score(&self, ply: usize) -> i32 {
+        score_from_tt(self.score, ply)
+    }
+}
+
+/// Fixed size hash table of search results indexed by the zobrist hash of a position.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    age: u8,
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Result<Self, String> {
+        if megabytes > MAX_SIZE_MB {
+            return Err(format!(
+                "hash size of {} MB is larger than the maximum of {} MB",
+                megabytes, MAX_SIZE_MB
+            ));
+        }
+        let bytes = megabytes
+            .checked_mul(1024 * 1024)
+            .ok_or_else(|| format!("hash size of {} MB is too large", megabytes))?;
+        let count = (bytes / std::mem::size_of::<Option<Entry>>()).max(1);
+        let mut entries = vec![];
+        entries
+            .try_reserve_exact(count)
+            .map_err(|e| format!("could not allocate {} MB for the hash: {}", megabytes, e))?;
+        entries.resize(count, None);
+        Ok(Self { entries, age: 0 })
+    }
+
+    /// Marks all current entries as belonging to a previous search so they get replaced first.
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&Entry> {
+        match &self.entries[self.index(key)] {
+            Some(entry) if entry.key == key => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// Stores a search result unless a deeper result of the same search is already there.
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: usize,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+        ply: usize,
+    ) {
+        let age = self.age;
+        let index = self.index(key);
+        let slot = &mut self.entries[index];
+        let replace = match slot {
+            None => true,
+            Some(old) => old.age != age || old.key == key || depth >= old.depth,
+        };
+        if !replace {
+            return;
+        }
+        // keep the best move of the position if the new result does not have one
+        let best_move = match (best_move, &mut *slot) {
+            (None, Some(old)) if old.key == key => old.best_move.take(),
+            (m, _) => m,
+        };
+        *slot = Some(Entry {
+            key,
+            best_move,
+            score: score_to_tt(score, ply),
+            depth,
+            bound,
+            age,
+        });
+    }
+}
+
+// Mate scores count the plies from the root but the table is shared between nodes at
+// different plies, so they are stored relative to the node instead.
+
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_MATE_PLY {
+        score + ply as i32
+    } else if score < -MATE_SCORE + MAX_MATE_PLY {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_MATE_PLY {
+        score - ply as i32
+    } else if score < -MATE_SCORE + MAX_MATE_PLY {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{score_from_tt, score_to_tt, Bound, TranspositionTable, MAX_SIZE_MB};
+    use crate::algos::minimax::MATE_SCORE;
+    use crate::chess::{Coord, Move};
+
+    #[test]
+    fn size_is_limited() {
+        assert!(TranspositionTable::new(MAX_SIZE_MB + 1).is_err());
+        assert!(TranspositionTable::new(usize::MAX).is_err());
+        assert_eq!(TranspositionTable::new(0).unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn mate_scores_are_stored_relative_to_the_node() {
+        // mate in 5 plies from the root found at ply 3 is a mate in 2 plies from the node
+        let stored = score_to_tt(MATE_SCORE - 5, 3);
+        assert_eq!(stored, MATE_SCORE - 2);
+        assert_eq!(score_from_tt(stored, 3), MATE_SCORE - 5);
+        // reaching the same position at ply 7 the mate is 9 plies from the root
+        assert_eq!(score_from_tt(stored, 7), MATE_SCORE - 9);
+        let stored = score_to_tt(-MATE_SCORE + 4, 1);
+        assert_eq!(score_from_tt(stored, 1), -MATE_SCORE + 4);
+        assert_eq!(score_from_tt(stored, 2), -MATE_SCORE + 5);
+        // other scores don't depend on the ply
+        assert_eq!(score_to_tt(150, 6), 150);
+        assert_eq!(score_from_tt(-150, 6), -150);
+    }
+
+    #[test]
+    fn replacement_by_depth_and_age() {
+        let mut tt = TranspositionTable::new(1).unwrap();
+        // both keys map to the same slot
+        let (a, b) = (7, 7 + tt.entries.len() as u64);
+        tt.store(a, 5, Bound::Exact, 10, None, 0);
+        tt.store(b, 3, Bound::Exact, 20, None, 0);
+        assert!(tt.probe(b).is_none());
+        assert_eq!(tt.probe(a).unwrap().depth, 5);
+        tt.store(b, 5, Bound::Lower, 20, None, 0);
+        assert_eq!(tt.probe(b).unwrap().score(0), 20);
+        assert!(tt.probe(a).is_none());
+        // results of a previous search are replaced even by shallower ones
+        tt.new_search();
+        tt.store(a, 1, Bound::Upper, 30, None, 0);
+        assert_eq!(tt.probe(a).unwrap().bound, Bound::Upper);
+        // the same position is always replaced
+        tt.store(a, 0, Bound::Exact, 40, None, 0);
+        assert_eq!(tt.probe(a).unwrap().score(0), 40);
+    }
+
+    #[test]
+    fn best_move_is_kept_for_the_same_position() {
+        let mut tt = TranspositionTable::new(1).unwrap();
+        let m = Move::Basic(Coord(6, 0), Coord(5, 2));
+        tt.store(1, 4, Bound::Lower, 10, Some(m.clone()), 0);
+        tt.store(1, 5, Bound::Upper, 0, None, 0);
+        let entry = tt.probe(1).unwrap();
+        assert_eq!(entry.best_move, Some(m));
+        assert_eq!(entry.depth, 5);
+        // another position in the slot does not inherit it
+        let other = 1 + tt.entries.len() as u64;
+        tt.store(other, 6, Bound::Upper, 0, None, 0);
+        assert_eq!(tt.probe(other).unwrap().best_move, None);
+    }
+}
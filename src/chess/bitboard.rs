@@ -0,0 +1,168 @@
+use super::moves::{BOARD_DIRECTIONS_DIAGONAL, KNIGHT_MOVES};
+use super::{Color, Coord, Piece};
+
+/// A set of tiles, bit `file + rank * 8` is set if the tile is included.
+pub type Bitboard = u64;
+
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(KNIGHT_MOVES);
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(BOARD_DIRECTIONS_DIAGONAL);
+/// Tiles attacked by a pawn, indexed by color and then the tile of the pawn.
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[Coord(-1, 1), Coord(1, 1)]),
+    leaper_attacks(&[Coord(-1, -1), Coord(1, -1)]),
+];
+/// All tiles in a direction from a tile (exclusive), indexed like `BOARD_DIRECTIONS_DIAGONAL`.
+pub const RAYS: [[Bitboard; 64]; 8] = rays();
+
+const fn leaper_attacks(offsets: &[Coord]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let file = (square % 8) as i8 + offsets[i].0;
+            let rank = (square / 8) as i8 + offsets[i].1;
+            if file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                table[square] |= 1 << (file + rank * 8);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn rays() -> [[Bitboard; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let d = &BOARD_DIRECTIONS_DIAGONAL[direction];
+        let mut square = 0;
+        while square < 64 {
+            let mut file = (square % 8) as i8 + d.0;
+            let mut rank = (square / 8) as i8 + d.1;
+            while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                table[direction][square] |= 1 << (file + rank * 8);
+                file += d.0;
+                rank += d.1;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+/// Tiles reachable by sliding from `square` in the directions `BOARD_DIRECTIONS_DIAGONAL[directions]`,
+/// including the first occupied tile of every ray.
+pub const fn ray_attacks(
+    square: usize,
+    occupied: Bitboard,
+    directions: std::ops::Range<usize>,
+) -> Bitboard {
+    let mut attacks = 0;
+    let mut direction = directions.start;
+    while direction < directions.end {
+        let ray = RAYS[direction][square];
+        let blockers = ray & occupied;
+        attacks |= if blockers == 0 {
+            ray
+        } else {
+            let d = &BOARD_DIRECTIONS_DIAGONAL[direction];
+            // the nearest blocker is the lowest bit for rays going up the board and the highest otherwise
+            let blocker = if d.0 + d.1 * 8 > 0 {
+                blockers.trailing_zeros()
+            } else {
+                63 - blockers.leading_zeros()
+            };
+            ray & !RAYS[direction][blocker as usize]
+        };
+        direction += 1;
+    }
+    attacks
+}
+
+/// The ray from `a` that passes through `b`, empty if they don't share a line.
+pub fn ray_through(a: usize, b: usize) -> Bitboard {
+    RAYS.iter()
+        .map(|ray| ray[a])
+        .find(|ray| ray & 1 << b != 0)
+        .unwrap_or(0)
+}
+
+/// Tiles strictly between two tiles on a line, empty if they don't share a line.
+pub fn between(a: usize, b: usize) -> Bitboard {
+    ray_through(a, b) & ray_through(b, a)
+}
+
+#[cfg(not(feature = "ray_attacks"))]
+pub use super::magic::{bishop_attacks, rook_attacks};
+
+/// How this build looks up sliding piece attacks, reported by `bench` so that the
+/// perft speed of builds with and without the `ray_attacks` feature can be compared.
+#[cfg(not(feature = "ray_attacks"))]
+pub const SLIDING_ATTACKS: &str = "magic bitboards";
+#[cfg(feature = "ray_attacks")]
+pub const SLIDING_ATTACKS: &str = "rays";
+
+#[cfg(feature = "ray_attacks")]
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    ray_attacks(square, occupied, 4..8)
+}
+
+#[cfg(feature = "ray_attacks")]
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    ray_attacks(square, occupied, 0..4)
+}
+
+pub fn queen_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+/// Iterates over the indices of all set bits.
+pub struct Squares(pub Bitboard);
+
+impl Iterator for Squares {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+impl Piece {
+    pub const ALL: [Piece; 6] = [
+        Piece::King,
+        Piece::Queen,
+        Piece::Knight,
+        Piece::Bishop,
+        Piece::Rook,
+        Piece::Pawn,
+    ];
+    /// Index into `Game::pieces`
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+    /// Material value in centipawns, the king is never captured and has none.
+    pub fn value(&self) -> i32 {
+        match self {
+            Piece::Pawn => 100,
+            Piece::Knight => 320,
+            Piece::Bishop => 330,
+            Piece::Rook => 500,
+            Piece::Queen => 900,
+            Piece::King => 0,
+        }
+    }
+}
+
+impl Color {
+    /// Index into `Game::colors`
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
@@ -9,12 +9,15 @@ impl Coord {
         }
         (self.0 + self.1 * 8) as usize
     }
+    pub fn from_index(index: usize) -> Self {
+        Coord((index % 8) as i8, (index / 8) as i8)
+    }
     pub fn offset(&self, off: &Coord) -> Self {
         Coord(self.0 + off.0, self.1 + off.1)
     }
     pub fn offset_in_place(&mut self, off: &Coord) {
-        self.0 = self.0 + off.0;
-        self.1 = self.1 + off.1;
+        self.0 += off.0;
+        self.1 += off.1;
     }
     pub fn is_valid(&self) -> bool {
         self.0 >= 0 && self.0 < 8 && self.1 >= 0 && self.1 < 8
@@ -1,4 +1,4 @@
-use super::{Color, Coord, Game};
+use super::{Color, Coord, Game, Outcome, Termination};
 use super::{Move, Piece};
 use std::fmt::Write;
 
@@ -8,7 +8,7 @@ impl std::fmt::Display for Game {
         for y in (0..8).rev() {
             for x in 0..8 {
                 board
-                    .write_char(match self.board[y * 8 + x] {
+                    .write_char(match self.get_tile(&Coord(x, y)) {
                         Some(t) => t.as_fen_char(),
                         None => ' ',
                     })
@@ -18,9 +18,8 @@ impl std::fmt::Display for Game {
         }
 
         f.write_fmt(format_args!(
-            "{}\n{}",
-            board,
-            format!("active color: {}", self.active_color),
+            "{}\nactive color: {}",
+            board, self.active_color,
         ))
     }
 }
@@ -74,3 +73,27 @@ impl std::fmt::Display for Coord {
         f.write_fmt(format_args!("{}", self.to_algebraic()))
     }
 }
+
+impl std::fmt::Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Termination::Checkmate => "checkmate",
+            Termination::Stalemate => "stalemate",
+            Termination::Repetition => "repetition",
+            Termination::FiftyMoveRule => "fifty-move rule",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::Resignation => "resignation",
+            Termination::Timeout => "timeout",
+            Termination::Agreement => "agreement",
+        })
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.winner {
+            Some(c) => f.write_fmt(format_args!("{} wins by {}", c, self.termination)),
+            None => f.write_fmt(format_args!("draw by {}", self.termination)),
+        }
+    }
+}
@@ -1,14 +1,18 @@
-use super::{Color, Coord, Game, Move, Piece, Tile};
+use super::{Color, Coord, Game, Move, Outcome, Piece, Tile};
 
 impl Game {
     pub fn from_fen(fen: &str) -> Result<Self, String> {
         let mut g = Self {
+            pieces: [0; 6],
+            colors: [0; 2],
             board: [None; 64],
             move_count: 0,
             moves_since_capture: 0,
             castling_avail: [false; 4],
             active_color: Color::White,
             en_passent_target: None,
+            hash: 0,
+            history: vec![],
         };
 
         for (fi, field) in fen.split(" ").enumerate() {
@@ -32,12 +36,12 @@ impl Game {
                                 '8' => x += 8,
                                 _ => {
                                     if x > 8 || rank < 0 {
-                                        return Err(format!(
+                                        return Err(
                                             "FEN field 'pieces' uses a board thats bigger than 8x8"
-                                        ));
+                                                .to_string(),
+                                        );
                                     }
-                                    g.board[Coord(x, rank as i8).index()] =
-                                        Some(Tile::from_fen_char(c)?);
+                                    g.set_tile(&Coord(x, rank), Some(Tile::from_fen_char(c)?));
                                     x += 1;
                                 }
                             }
@@ -70,7 +74,20 @@ impl Game {
                 }
                 3 => {
                     if field != "-" {
-                        g.en_passent_target = Some(Coord::from_algebraic(field)?)
+                        let target = Coord::from_algebraic(field)?;
+                        // the pawn that was pushed two tiles by the other color passed the target
+                        let them = g.active_color.opponent();
+                        let pawn = target.offset(&them.get_direction());
+                        if target.1 != them.get_home_rank() + 2 * them.get_direction().1
+                            || g.get_tile(&target).is_some()
+                            || g.get_tile(&pawn) != Some(Tile(them, Piece::Pawn))
+                        {
+                            return Err(format!(
+                                "FEN field 'en passent target' is not behind a pawn that was just pushed two tiles: {:?}",
+                                field
+                            ));
+                        }
+                        g.en_passent_target = Some(target)
                     }
                 }
                 4 => {
@@ -101,21 +118,18 @@ impl Game {
                 }
             }
         }
+        g.hash = g.compute_hash();
         Ok(g)
     }
     pub fn to_fen(&self) -> String {
         let mut output = String::new();
         let mut empty_count: Option<usize> = None;
-        for rank in 0..8 {
-            if let Some(n) = empty_count {
-                output += format!("{}", n).as_str();
-                empty_count = None
-            }
-            if rank != 0 {
+        for rank in (0..8).rev() {
+            if rank != 7 {
                 output += "/"
             }
             for file in 0..8 {
-                let tile = self.board[Coord(file, rank).index()];
+                let tile = self.get_tile(&Coord(file, rank));
                 match tile {
                     Some(t) => {
                         if let Some(n) = empty_count {
@@ -130,6 +144,10 @@ impl Game {
                     },
                 }
             }
+            if let Some(n) = empty_count {
+                output += format!("{}", n).as_str();
+                empty_count = None
+            }
         }
         output += " ";
         output += format!("{}", self.active_color.as_fen_color()).as_str();
@@ -148,7 +166,7 @@ impl Game {
             if self.castling_avail[3] {
                 r += "q";
             }
-            if r.len() == 0 {
+            if r.is_empty() {
                 r += "-"
             }
             output += r.as_str();
@@ -160,7 +178,7 @@ impl Game {
         };
         output += temp.as_str();
         output += format!(" {} {}", self.moves_since_capture, self.move_count).as_str();
-        return output;
+        output
     }
 }
 
@@ -173,6 +191,17 @@ impl Color {
     }
 }
 
+impl Outcome {
+    /// The result tag used by PGN
+    pub fn as_pgn_result(&self) -> &'static str {
+        match self.winner {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
 impl Tile {
     pub fn from_fen_char(s: char) -> Result<Self, String> {
         Ok(match s {
@@ -208,7 +237,7 @@ impl Tile {
         if self.0 == Color::White {
             c = c.to_uppercase().to_string().chars().nth(0).unwrap();
         }
-        return c;
+        c
     }
 }
 
@@ -249,28 +278,166 @@ impl Coord {
                 ))
             }
         };
-        return Ok(Coord(x, y));
+        Ok(Coord(x, y))
     }
     pub fn to_algebraic(&self) -> String {
         let files = &['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
         let ranks = &['1', '2', '3', '4', '5', '6', '7', '8'];
-        return format!("{}{}", files[self.file_index()], ranks[self.rank_index()]);
+        format!("{}{}", files[self.file_index()], ranks[self.rank_index()])
     }
 }
 
+const CASTLING_CHARS: [char; 4] = ['K', 'Q', 'k', 'q'];
+
 impl Move {
     pub fn serialize(&self) -> String {
-        format!(
-            "{}",
-            match self {
-                Move::Basic(from, to) => format!("b,{}-{}", from, to),
-                Move::Castle(color, side) => format!("c,{},{}", color, side),
-                Move::EnPassent(from, to) => format!("e,{}-{}", from, to),
-                Move::PawnPromotion(from, to, a) => format!("p,{}-{},{}", from, to, a),
+        match self {
+            Move::Basic(from, to) => format!("b,{}-{}", from, to),
+            Move::Castle(color, king_side) => {
+                format!("r,{}", CASTLING_CHARS[color.castling_index(*king_side)])
+            }
+            Move::EnPassent(from, to) => format!("e,{}-{}", from, to),
+            Move::PawnPromotion(from, to, a) => {
+                // the color of the pawn is implied by the rank it is promoted on
+                let color = if to.1 == 7 {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                format!("p,{}-{},{}", from, to, Tile(color, *a).as_fen_char())
+            }
+        }
+    }
+    pub fn deserialize(s: &str) -> Result<Self, String> {
+        let fields = s.split(',').collect::<Vec<_>>();
+        let expected_fields = match fields[0] {
+            "b" | "e" | "r" => 2,
+            "p" => 3,
+            _ => return Err(format!("Move has unknown type: {:?}", fields[0])),
+        };
+        if fields.len() != expected_fields {
+            return Err(format!(
+                "Move of type {:?} needs {} fields but has {}: {:?}",
+                fields[0],
+                expected_fields,
+                fields.len(),
+                s
+            ));
+        }
+        if fields[0] == "r" {
+            let mut chars = fields[1].chars();
+            return match (chars.next(), chars.next()) {
+                (Some('K'), None) => Ok(Move::Castle(Color::White, true)),
+                (Some('Q'), None) => Ok(Move::Castle(Color::White, false)),
+                (Some('k'), None) => Ok(Move::Castle(Color::Black, true)),
+                (Some('q'), None) => Ok(Move::Castle(Color::Black, false)),
+                _ => Err(format!(
+                    "Castling side has to be one of KQkq: {:?}",
+                    fields[1]
+                )),
+            };
+        }
+        let (from, to) = match fields[1].split_once('-') {
+            Some((from, to)) => (Coord::from_algebraic(from)?, Coord::from_algebraic(to)?),
+            None => {
+                return Err(format!(
+                    "Move coordinates are not seperated by '-': {:?}",
+                    fields[1]
+                ))
             }
-        )
+        };
+        Ok(match fields[0] {
+            "b" => Move::Basic(from, to),
+            "e" => Move::EnPassent(from, to),
+            _ => {
+                let mut chars = fields[2].chars();
+                let tile = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Tile::from_fen_char(c)?,
+                    _ => {
+                        return Err(format!(
+                            "Promotion piece has to be a single character: {:?}",
+                            fields[2]
+                        ))
+                    }
+                };
+                Move::PawnPromotion(from, to, tile.1)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::{Color, Coord, Game, Move, Outcome, Piece, Termination};
+
+    #[test]
+    fn move_serialization_round_trip() {
+        let moves = [
+            ("b,e5-f5", Move::Basic(Coord(4, 4), Coord(5, 4))),
+            ("r,K", Move::Castle(Color::White, true)),
+            ("r,q", Move::Castle(Color::Black, false)),
+            (
+                "p,a7-a8,Q",
+                Move::PawnPromotion(Coord(0, 6), Coord(0, 7), Piece::Queen),
+            ),
+            (
+                "p,b2-a1,n",
+                Move::PawnPromotion(Coord(1, 1), Coord(0, 0), Piece::Knight),
+            ),
+            ("e,c3-d4", Move::EnPassent(Coord(2, 2), Coord(3, 3))),
+        ];
+        for (s, m) in moves.iter() {
+            assert_eq!(m.serialize(), *s);
+            assert_eq!(Move::deserialize(s), Ok(m.clone()));
+        }
     }
-    pub fn deserialize(s: &str) -> Self {
-        todo!()
+
+    #[test]
+    fn en_passent_target_validation() {
+        assert!(Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").is_ok());
+        assert!(Game::from_fen("4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1").is_ok());
+        for fen in &[
+            // the king is not a pawn that can be captured en passent
+            "8/3Pk3/8/8/8/8/8/4K3 w - e8 0 1",
+            // wrong rank for the side to move
+            "4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 1",
+            "4k3/8/8/8/3Pp3/8/8/4K3 w - d3 0 1",
+            // no pawn in front of the target
+            "4k3/8/8/4P3/8/8/8/4K3 w - d6 0 1",
+            "4k3/8/8/3nP3/8/8/8/4K3 w - d6 0 1",
+            // the target is occupied
+            "4k3/8/3n4/3pP3/8/8/8/4K3 w - d6 0 1",
+        ] {
+            assert!(Game::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn move_deserialization_errors() {
+        for s in &[
+            "",
+            "x,e2-e4",
+            "b,e2e4",
+            "b,e2-e9",
+            "b,e2-e4,Q",
+            "r,X",
+            "r,KQ",
+            "p,a7-a8",
+            "p,a7-a8,X",
+        ] {
+            assert!(Move::deserialize(s).is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn outcome_pgn_result() {
+        let white = Outcome::win(Color::White, Termination::Checkmate);
+        let black = Outcome::win(Color::Black, Termination::Resignation);
+        let draw = Outcome::draw(Termination::Agreement);
+        assert_eq!(white.as_pgn_result(), "1-0");
+        assert_eq!(black.as_pgn_result(), "0-1");
+        assert_eq!(draw.as_pgn_result(), "1/2-1/2");
+        assert_eq!(format!("{}", black), "black wins by resignation");
+        assert_eq!(format!("{}", draw), "draw by agreement");
     }
 }
@@ -0,0 +1,180 @@
+use super::bitboard::{between, bishop_attacks, ray_through, rook_attacks, Bitboard, Squares};
+use super::{Color, Coord, Game, Move, Piece, Tile};
+
+// Instead of trying every pseudo legal move on the board, the checkers and pinned pieces of
+// the active color are found once and restrict where each piece may move:
+// - the king may only move to tiles that are not attacked once it left its tile
+// - in double check only the king can move
+// - in single check the other pieces have to capture the checker or block its line
+// - pinned pieces have to stay on the line between their king and the pinner
+// En passent removes two pieces from a rank at once, so it is tested by looking for
+// attacks on the king with the resulting occupancy.
+
+impl Game {
+    /// Generates the legal moves of the active color's pieces on the tiles in `from`.
+    pub fn generate_legal_moves(&self, from: Bitboard) -> Vec<Move> {
+        let mut moves = vec![];
+        self.generate(from, false, &mut moves);
+        moves
+    }
+
+    /// Like `generate_legal_moves` but appends to `moves`, so a buffer can be reused.
+    pub fn push_legal_moves(&self, from: Bitboard, moves: &mut Vec<Move>) {
+        self.generate(from, false, moves)
+    }
+
+    /// Legal captures and promotions of the active color, for searching until the position is quiet.
+    pub fn get_captures(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        self.generate(self.colors[self.active_color.index()], true, &mut moves);
+        moves
+    }
+
+    fn generate(&self, from: Bitboard, captures_only: bool, moves: &mut Vec<Move>) {
+        let color = self.active_color;
+        let them = color.opponent();
+        let occupied = self.get_occupied();
+        let noisy = match captures_only {
+            true => self.colors[them.index()],
+            false => !0,
+        };
+        // quiet pawn moves are still noisy if they promote
+        let promotion_rank = 0xff << (them.get_home_rank() * 8);
+        let king_bb = self.get_bitboard(color, Piece::King);
+        let king = king_bb.trailing_zeros() as usize;
+
+        let (checkers, pinned) = match king_bb {
+            // positions without a king can not be in check
+            0 => (0, 0),
+            _ => (
+                self.attackers_with_occupancy(king, them, occupied),
+                self.pinned_pieces(color),
+            ),
+        };
+
+        if from & king_bb != 0 {
+            let c = Coord::from_index(king);
+            let tile = Tile(color, Piece::King);
+            // the king must not block the attack on the tile it moves to
+            let without_king = occupied & !king_bb;
+            let targets = Squares(self.get_targets(&c, tile) & noisy)
+                .filter(|sq| self.attackers_with_occupancy(*sq, them, without_king) == 0)
+                .fold(0, |b, sq| b | 1 << sq);
+            if checkers == 0 && !captures_only {
+                moves.append(&mut self.get_castling_moves(color));
+            }
+            self.push_moves(&c, tile, targets, moves);
+        }
+
+        if checkers.count_ones() > 1 {
+            return;
+        }
+        let evasions = match checkers {
+            0 => !0,
+            _ => checkers | between(king, checkers.trailing_zeros() as usize),
+        };
+        for square in Squares(from & self.colors[color.index()] & !king_bb) {
+            let c = Coord::from_index(square);
+            let tile = match self.get_tile(&c) {
+                Some(t) => t,
+                None => continue,
+            };
+            let mut allowed = evasions
+                & match tile.1 {
+                    Piece::Pawn => noisy | promotion_rank,
+                    _ => noisy,
+                };
+            if pinned & 1 << square != 0 {
+                allowed &= ray_through(king, square);
+            }
+            if tile.1 == Piece::Pawn {
+                if let Some(m) = self.get_en_passent_move(&c, color) {
+                    if king_bb == 0 || self.en_passent_is_legal(&m, king) {
+                        moves.push(m);
+                    }
+                }
+            }
+            self.push_moves(&c, tile, self.get_targets(&c, tile) & allowed, moves);
+        }
+    }
+
+    /// Pieces of `color` that can not leave the line between their king and an enemy slider.
+    pub fn pinned_pieces(&self, color: Color) -> Bitboard {
+        let them = color.opponent();
+        let king = self.get_bitboard(color, Piece::King).trailing_zeros() as usize;
+        let queens = self.get_bitboard(them, Piece::Queen);
+        // the sliders that would attack the king if none of the own pieces were there
+        let their_pieces = self.colors[them.index()];
+        let snipers = rook_attacks(king, their_pieces)
+            & (self.get_bitboard(them, Piece::Rook) | queens)
+            | bishop_attacks(king, their_pieces)
+                & (self.get_bitboard(them, Piece::Bishop) | queens);
+        let mut pinned = 0;
+        for sniper in Squares(snipers) {
+            let blockers = between(king, sniper) & self.colors[color.index()];
+            if blockers.count_ones() == 1 {
+                pinned |= blockers;
+            }
+        }
+        pinned
+    }
+
+    fn en_passent_is_legal(&self, m: &Move, king: usize) -> bool {
+        let (from, to) = match m {
+            Move::EnPassent(from, to) => (from, to),
+            _ => return false,
+        };
+        let captured = Coord(to.0, from.1).index();
+        let occupied =
+            self.get_occupied() & !(1 << from.index()) & !(1 << captured) | 1 << to.index();
+        self.attackers_with_occupancy(king, self.active_color.opponent(), occupied)
+            & !(1 << captured)
+            == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::chess::perft::PERFT_POSITIONS;
+    use crate::chess::{Game, Move};
+
+    fn is_noisy(game: &Game, m: &Move) -> bool {
+        matches!(m, Move::PawnPromotion(..)) || game.get_captured_piece(m).is_some()
+    }
+
+    fn sorted(moves: Vec<Move>) -> Vec<String> {
+        let mut moves = moves.iter().map(|m| m.serialize()).collect::<Vec<_>>();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn legal_moves_and_captures_in_random_games() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for (fen, _) in PERFT_POSITIONS {
+            for _ in 0..20 {
+                let mut game = Game::from_fen(fen).unwrap();
+                for _ in 0..40 {
+                    let legal = game.get_all_possible_moves();
+                    let unchecked = game.get_all_possible_moves_unchecked();
+                    let filtered = game.filter_legal_moves(unchecked);
+                    assert_eq!(sorted(legal.clone()), sorted(filtered), "{}", game.to_fen());
+                    let noisy = legal.iter().filter(|m| is_noisy(&game, m)).cloned();
+                    assert_eq!(
+                        sorted(game.get_captures()),
+                        sorted(noisy.collect()),
+                        "{}",
+                        game.to_fen()
+                    );
+                    if legal.is_empty() {
+                        break;
+                    }
+                    game.make_move_unchecked(&legal[rng.gen_range(0..legal.len())]);
+                }
+            }
+        }
+    }
+}
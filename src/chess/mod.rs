@@ -1,7 +1,15 @@
+pub mod bitboard;
 pub mod coord;
 pub mod display;
 pub mod fen;
+pub mod legal;
+pub mod magic;
 pub mod moves;
+pub mod perft;
+pub mod see;
+pub mod zobrist;
+
+use bitboard::Bitboard;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Piece {
@@ -21,14 +29,33 @@ pub enum Color {
 #[derive(Debug, PartialEq, Eq)]
 pub enum GameState {
     Normal,
-    Draw,
+    Check(Color), // the color that is in check
+    // the game is over if the active color claims the draw, the color is set if it is in check
+    ClaimableDraw(Termination, Option<Color>),
+    Over(Outcome),
+}
+
+/// How a finished game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub winner: Option<Color>, // None for a draw
+    pub termination: Termination,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
     Stalemate,
-    Check(Color),
-    Checkmate(Color),
+    Repetition,           // claimable after three times, automatic after five
+    FiftyMoveRule,        // claimable after fifty moves, automatic after seventy-five
+    InsufficientMaterial, // no sequence of moves can lead to checkmate
+    Resignation,
+    Timeout,
+    Agreement,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Tile(Color, Piece);
+pub struct Tile(pub Color, pub Piece);
 
 // x file, y rank
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -37,17 +64,32 @@ pub struct Coord(pub i8, pub i8);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Move {
     Basic(Coord, Coord),
-    Castle(Color, bool),
+    Castle(Color, bool), // true for king side
     EnPassent(Coord, Coord),
     PawnPromotion(Coord, Coord, Piece),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Game {
-    board: [Option<Tile>; 64],
+    pieces: [Bitboard; 6],     // indexed by Piece::index
+    colors: [Bitboard; 2],     // indexed by Color::index
+    board: [Option<Tile>; 64], // the same pieces by Coord::index, for get_tile
     active_color: Color,
     castling_avail: [bool; 4], // KQkq
     move_count: u16,
     en_passent_target: Option<Coord>,
+    moves_since_capture: u16, // halfmove clock, reset by captures and pawn moves
+    hash: u64,                // zobrist hash of everything above
+    history: Vec<u64>,        // hashes of all previous positions
+}
+
+/// Everything needed to revert a move that can not be derived from the move itself.
+#[derive(Debug, Clone)]
+pub struct Undo {
+    captured: Option<Tile>,
+    castling_avail: [bool; 4],
+    en_passent_target: Option<Coord>,
     moves_since_capture: u16,
+    move_count: u16,
+    hash: u64,
 }
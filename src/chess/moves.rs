@@ -1,6 +1,11 @@
-use super::{Color, Coord, Game, GameState, Move, Piece, Tile};
+use super::bitboard::{
+    bishop_attacks, queen_attacks, rook_attacks, Bitboard, Squares, KING_ATTACKS, KNIGHT_ATTACKS,
+    PAWN_ATTACKS,
+};
+use super::{
+    zobrist, Color, Coord, Game, GameState, Move, Outcome, Piece, Termination, Tile, Undo,
+};
 
-pub const BOARD_DIRECTIONS: &[Coord; 4] = &[Coord(0, 1), Coord(0, -1), Coord(1, 0), Coord(-1, 0)];
 pub const BOARD_DIRECTIONS_DIAGONAL: &[Coord; 8] = &[
     Coord(1, 1),
     Coord(1, -1),
@@ -11,8 +16,6 @@ pub const BOARD_DIRECTIONS_DIAGONAL: &[Coord; 8] = &[
     Coord(1, 0),
     Coord(-1, 0),
 ];
-pub const BOARD_DIRECTIONS_ONLY_DIAGONAL: &[Coord; 4] =
-    &[Coord(1, 1), Coord(1, -1), Coord(-1, 1), Coord(-1, -1)];
 pub const KNIGHT_MOVES: &[Coord; 8] = &[
     Coord(2, 1),
     Coord(2, -1),
@@ -23,224 +26,444 @@ pub const KNIGHT_MOVES: &[Coord; 8] = &[
     Coord(1, -2),
     Coord(-1, -2),
 ];
+/// a1, c1, ..., b2, d2, ...
+pub const DARK_TILES: Bitboard = 0xaa55_aa55_aa55_aa55;
+pub const PROMOTION_PIECES: &[Piece; 4] =
+    &[Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
 
 impl Game {
-    pub fn get_all_possible_moves(&self) -> Vec<Move> {
-        let mut moves = vec![];
-        for file in 0..8 {
-            for rank in 0..8 {
-                let c = Coord(file, rank);
-                if let Some(tile) = self.board[c.index()] {
-                    if tile.0 == self.active_color {
-                        moves.append(&mut self.get_possible_moves(&c))
-                    }
-                }
-            }
-        }
-        return moves;
+    pub fn get_tile(&self, c: &Coord) -> Option<Tile> {
+        self.board[c.index()]
     }
 
-    pub fn get_all_possible_moves_unchecked(&self) -> Vec<Move> {
-        let mut moves = vec![];
-        for file in 0..8 {
-            for rank in 0..8 {
-                let c = Coord(file, rank);
-                if let Some(tile) = self.board[c.index()] {
-                    if tile.0 == self.active_color {
-                        moves.append(&mut self.get_possible_moves_unchecked(&c))
-                    }
-                }
-            }
+    pub fn set_tile(&mut self, c: &Coord, tile: Option<Tile>) {
+        let mask = 1 << c.index();
+        if let Some(old) = self.board[c.index()] {
+            self.hash ^= zobrist::tile_key(old, c.index());
+            self.pieces[old.1.index()] &= !mask;
+            self.colors[old.0.index()] &= !mask;
+        }
+        if let Some(new) = tile {
+            self.hash ^= zobrist::tile_key(new, c.index());
+            self.pieces[new.1.index()] |= mask;
+            self.colors[new.0.index()] |= mask;
         }
-        return moves;
+        self.board[c.index()] = tile;
     }
 
-    pub fn get_possible_moves(&self, c: &Coord) -> Vec<Move> {
-        return self
-            .get_possible_moves_unchecked(c)
-            .into_iter()
-            // .inspect(|m| println!("{} {}", self.move_results_in_check(m), m))
-            .filter(|m| !self.move_results_in_check(m))
-            .collect::<Vec<Move>>();
+    pub fn get_bitboard(&self, color: Color, piece: Piece) -> Bitboard {
+        self.colors[color.index()] & self.pieces[piece.index()]
     }
 
-    pub fn get_possible_moves_unchecked(&self, c: &Coord) -> Vec<Move> {
-        let tile = match self.board[c.index()] {
-            Some(t) => t,
-            None => return vec![],
-        };
-        let mut moves: Vec<Move> = vec![];
-        let mut basic_moves_targets: Vec<Coord> = vec![];
+    pub fn get_occupied(&self) -> Bitboard {
+        self.colors[Color::White.index()] | self.colors[Color::Black.index()]
+    }
 
-        match tile.1 {
-            Piece::Queen => {
-                for d in BOARD_DIRECTIONS_ONLY_DIAGONAL {
-                    basic_moves_targets.append(&mut self.get_consecutive_capture_tiles(c, d))
-                }
-            }
-            Piece::King => {
-                for d in BOARD_DIRECTIONS_DIAGONAL {
-                    basic_moves_targets.push(c.offset(d));
-                }
-            }
-            Piece::Knight => {
-                for d in KNIGHT_MOVES {
-                    basic_moves_targets.push(c.offset(d));
-                }
-            }
-            Piece::Bishop => {
-                for d in BOARD_DIRECTIONS_ONLY_DIAGONAL {
-                    basic_moves_targets.append(&mut self.get_consecutive_capture_tiles(c, d))
-                }
-            }
-            Piece::Rook => {
-                for d in BOARD_DIRECTIONS {
-                    basic_moves_targets.append(&mut self.get_consecutive_capture_tiles(c, d))
-                }
-            }
+    pub fn get_active_color(&self) -> Color {
+        self.active_color
+    }
+
+    pub fn get_all_possible_moves(&self) -> Vec<Move> {
+        self.generate_legal_moves(self.colors[self.active_color.index()])
+    }
+
+    /// Legal moves of the piece on the tile, empty if it doesn't belong to the active color.
+    pub fn get_possible_moves(&self, c: &Coord) -> Vec<Move> {
+        self.generate_legal_moves(1 << c.index())
+    }
+
+    /// Tiles the piece can move to, ignoring castling, en passent and checks.
+    pub fn get_targets(&self, c: &Coord, tile: Tile) -> Bitboard {
+        let square = c.index();
+        let occupied = self.get_occupied();
+        let targets = match tile.1 {
+            Piece::Queen => queen_attacks(square, occupied),
+            Piece::Rook => rook_attacks(square, occupied),
+            Piece::Bishop => bishop_attacks(square, occupied),
+            Piece::Knight => KNIGHT_ATTACKS[square],
+            Piece::King => KING_ATTACKS[square],
             Piece::Pawn => {
-                basic_moves_targets.push(c.offset(&tile.0.get_direction()));
-                if c.1 == 1 && tile.0 == Color::White {
-                    basic_moves_targets.push(c.offset(&tile.0.get_direction().mul(2)));
+                let mut targets =
+                    PAWN_ATTACKS[tile.0.index()][square] & self.colors[tile.0.opponent().index()];
+                let forward = tile.0.get_direction();
+                let single = c.offset(&forward);
+                if single.is_valid() && occupied & 1 << single.index() == 0 {
+                    targets |= 1 << single.index();
+                    let double = single.offset(&forward);
+                    if c.1 == tile.0.get_home_rank() + forward.1
+                        && occupied & 1 << double.index() == 0
+                    {
+                        targets |= 1 << double.index();
+                    }
                 }
-                if c.1 == 7 && tile.0 == Color::Black {
-                    basic_moves_targets.push(c.offset(&tile.0.get_direction().mul(2)));
+                targets
+            }
+        };
+        // kings are never captured, the game ends before
+        targets & !self.colors[tile.0.index()] & !self.pieces[Piece::King.index()]
+    }
+
+    pub fn get_en_passent_move(&self, c: &Coord, color: Color) -> Option<Move> {
+        let target = self.en_passent_target.as_ref()?;
+        // the target is empty and the pawn to capture stands next to the capturing one
+        if PAWN_ATTACKS[color.index()][c.index()] & 1 << target.index() != 0
+            && self.get_tile(target).is_none()
+            && self.get_tile(&Coord(target.0, c.1)) == Some(Tile(color.opponent(), Piece::Pawn))
+        {
+            Some(Move::EnPassent(c.clone(), target.clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Adds a move to every target, pawns reaching the last rank get one for every promotion.
+    pub fn push_moves(&self, c: &Coord, tile: Tile, targets: Bitboard, moves: &mut Vec<Move>) {
+        for target in Squares(targets).map(Coord::from_index) {
+            if tile.1 == Piece::Pawn && target.1 == tile.0.opponent().get_home_rank() {
+                for p in PROMOTION_PIECES {
+                    moves.push(Move::PawnPromotion(c.clone(), target.clone(), *p));
                 }
-                // TODO en passent
+            } else {
+                moves.push(Move::Basic(c.clone(), target));
             }
         }
-        let mut basic_moves = basic_moves_targets
-            .iter()
-            .filter(|t| t.is_valid())
-            .filter(|t| self.can_capture_tile(c, t))
-            .map(|t| Move::Basic(c.clone(), t.clone()))
-            .collect::<Vec<_>>();
+    }
 
-        moves.append(&mut basic_moves);
-        return moves;
+    /// The piece the move captures, if any.
+    pub fn get_captured_piece(&self, m: &Move) -> Option<Piece> {
+        match m {
+            Move::Basic(_, to) | Move::PawnPromotion(_, to, _) => match self.get_tile(to) {
+                Some(Tile(color, piece)) if color != self.active_color => Some(piece),
+                _ => None,
+            },
+            Move::EnPassent(_, _) => Some(Piece::Pawn),
+            Move::Castle(_, _) => None,
+        }
     }
 
-    pub fn move_results_in_check(&self, m: &Move) -> bool {
-        let mut branch = self.clone();
-        branch.make_move_unchecked(m);
-        return branch.is_check().is_some();
+    pub fn is_king_attacked(&self, color: Color) -> bool {
+        match self.get_bitboard(color, Piece::King) {
+            0 => false,
+            king => self.is_square_attacked(
+                &Coord::from_index(king.trailing_zeros() as usize),
+                color.opponent(),
+            ),
+        }
     }
 
     pub fn make_move(&mut self, m: &Move) -> Result<GameState, String> {
-        if self.move_results_in_check(m) {
-            return Err(format!("move will result in check"));
+        if let Move::PawnPromotion(_, _, a) = m {
+            if !PROMOTION_PIECES.contains(a) {
+                return Err(format!("pawns can not be promoted to a {}", a));
+            }
         }
         let possible_moves = self.get_possible_moves(&m.get_source_coord());
-        if !possible_moves.iter().any(|mc| *mc == *m) {
-            return Err(format!(
-                "move is not part of the set of all possible moves."
-            ));
+        if !possible_moves.contains(m) {
+            return Err("move is not part of the set of all possible moves.".to_string());
         }
         self.make_move_unchecked(m);
         Ok(self.state())
     }
 
-    pub fn make_move_unchecked(&mut self, m: &Move) {
-        let capture = match m {
+    /// Performs a move without checking if it is possible.
+    /// The returned `Undo` can be passed to `unmake_move` to revert it.
+    pub fn make_move_unchecked(&mut self, m: &Move) -> Undo {
+        let undo = Undo {
+            captured: match m {
+                Move::EnPassent(from, to) => self.get_tile(&Coord(to.0, from.1)),
+                _ => m.get_capture_target().and_then(|c| self.get_tile(&c)),
+            },
+            castling_avail: self.castling_avail,
+            en_passent_target: self.en_passent_target.clone(),
+            moves_since_capture: self.moves_since_capture,
+            move_count: self.move_count,
+            hash: self.hash,
+        };
+        let pawn_move = match m {
+            Move::Basic(from, _) => self.get_tile(from).map(|t| t.1) == Some(Piece::Pawn),
+            Move::Castle(_, _) => false,
+            Move::EnPassent(_, _) | Move::PawnPromotion(_, _, _) => true,
+        };
+        self.history.push(self.hash);
+        self.hash ^= zobrist::castling_key(&self.castling_avail) ^ self.en_passent_hash();
+        match m {
             Move::Basic(from, to) => {
-                let tile = self.board[from.index()];
-                let capture = self.board[to.index()].is_some();
-                self.board[to.index()] = tile;
-                self.board[from.index()] = None;
-                capture
+                self.set_tile(to, self.get_tile(from));
+                self.set_tile(from, None);
             }
-            Move::Castle(king_from, king_to) => {
-                // TODO castling
-                todo!();
+            Move::Castle(color, king_side) => {
+                let rank = color.get_home_rank();
+                let (rook_from, rook_to, king_to) = match king_side {
+                    true => (7, 5, 6),
+                    false => (0, 3, 2),
+                };
+                self.set_tile(&Coord(4, rank), None);
+                self.set_tile(&Coord(rook_from, rank), None);
+                self.set_tile(&Coord(king_to, rank), Some(Tile(*color, Piece::King)));
+                self.set_tile(&Coord(rook_to, rank), Some(Tile(*color, Piece::Rook)));
             }
             Move::EnPassent(from, to) => {
-                // TODO en passent
-                todo!();
+                self.set_tile(to, self.get_tile(from));
+                self.set_tile(from, None);
+                self.set_tile(&Coord(to.0, from.1), None);
             }
             Move::PawnPromotion(from, to, a) => {
-                todo!();
+                self.set_tile(to, Some(Tile(self.active_color, *a)));
+                self.set_tile(from, None);
+            }
+        };
+        let capture = undo.captured.is_some();
+        self.en_passent_target = match m {
+            Move::Basic(from, to)
+                if self.get_tile(to).map(|t| t.1) == Some(Piece::Pawn)
+                    && (to.1 - from.1).abs() == 2 =>
+            {
+                Some(Coord(from.0, (from.1 + to.1) / 2))
             }
+            _ => None,
         };
-        if capture {
+        self.update_castling_avail(&m.get_source_coord());
+        if let Some(target) = m.get_capture_target() {
+            self.update_castling_avail(&target);
+        }
+        if capture || pawn_move {
             self.moves_since_capture = 0;
+        } else {
+            self.moves_since_capture += 1;
         }
-        if self.active_color == Color::White {
+        if self.active_color == Color::Black {
             self.move_count += 1;
-            if !capture {
-                self.moves_since_capture += 1;
-            }
         }
         self.active_color = self.active_color.opponent();
+        self.hash ^= zobrist::castling_key(&self.castling_avail)
+            ^ self.en_passent_hash()
+            ^ zobrist::side_key();
+        undo
+    }
+
+    /// Reverts `m`, which has to be the last move made with `make_move_unchecked`.
+    pub fn unmake_move(&mut self, m: &Move, undo: &Undo) {
+        self.active_color = self.active_color.opponent();
+        match m {
+            Move::Basic(from, to) => {
+                self.set_tile(from, self.get_tile(to));
+                self.set_tile(to, undo.captured);
+            }
+            Move::Castle(color, king_side) => {
+                let rank = color.get_home_rank();
+                let (rook_from, rook_to, king_to) = match king_side {
+                    true => (7, 5, 6),
+                    false => (0, 3, 2),
+                };
+                self.set_tile(&Coord(king_to, rank), None);
+                self.set_tile(&Coord(rook_to, rank), None);
+                self.set_tile(&Coord(4, rank), Some(Tile(*color, Piece::King)));
+                self.set_tile(&Coord(rook_from, rank), Some(Tile(*color, Piece::Rook)));
+            }
+            Move::EnPassent(from, to) => {
+                self.set_tile(from, self.get_tile(to));
+                self.set_tile(to, None);
+                self.set_tile(&Coord(to.0, from.1), undo.captured);
+            }
+            Move::PawnPromotion(from, to, _) => {
+                self.set_tile(from, Some(Tile(self.active_color, Piece::Pawn)));
+                self.set_tile(to, undo.captured);
+            }
+        }
+        self.castling_avail = undo.castling_avail;
+        self.en_passent_target = undo.en_passent_target.clone();
+        self.moves_since_capture = undo.moves_since_capture;
+        self.move_count = undo.move_count;
+        self.hash = undo.hash;
+        self.history.pop();
+    }
+
+    /// Counts how often the current position occured, including now.
+    /// Only positions since the last capture or pawn move can be repeated.
+    pub fn repetition_count(&self) -> usize {
+        1 + self
+            .history
+            .iter()
+            .rev()
+            .take(self.moves_since_capture as usize)
+            .filter(|h| **h == self.hash)
+            .count()
+    }
+
+    /// Draws that end the game without any player claiming them.
+    pub fn automatic_draw(&self) -> Option<Termination> {
+        if self.is_insufficient_material() {
+            Some(Termination::InsufficientMaterial)
+        } else if self.repetition_count() >= 5 {
+            Some(Termination::Repetition)
+        } else if self.moves_since_capture >= 150 {
+            Some(Termination::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Draws the active color may claim.
+    pub fn claimable_draw(&self) -> Option<Termination> {
+        if self.repetition_count() >= 3 {
+            Some(Termination::Repetition)
+        } else if self.moves_since_capture >= 100 {
+            Some(Termination::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if neither side can ever checkmate: only kings and either a single knight or
+    /// bishop, or any number of bishops that all stand on tiles of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy = [Piece::Pawn, Piece::Rook, Piece::Queen]
+            .iter()
+            .fold(0, |b, p| b | self.pieces[p.index()]);
+        if heavy != 0 {
+            return false;
+        }
+        let knights = self.pieces[Piece::Knight.index()];
+        let bishops = self.pieces[Piece::Bishop.index()];
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+        knights == 0 && (bishops & DARK_TILES == 0 || bishops & !DARK_TILES == 0)
     }
 
-    pub fn can_capture_tile(&self, source: &Coord, target: &Coord) -> bool {
-        let source_tile =
-            self.board[source.index()].expect("Nothing is not able to capture anything");
-        let target_tile = self.board[target.index()];
-        return match target_tile {
-            None => true,
-            Some(t) => {
-                if t.0 == source_tile.0 || t.1 == Piece::King {
-                    return false;
+    pub fn get_halfmove_clock(&self) -> u16 {
+        self.moves_since_capture
+    }
+
+    /// Revokes all castling rights that depend on the king or rook starting at `c`.
+    /// Needs to be called with the source and target of every move.
+    pub fn update_castling_avail(&mut self, c: &Coord) {
+        for color in &[Color::White, Color::Black] {
+            if c.1 != color.get_home_rank() {
+                continue;
+            }
+            match c.0 {
+                4 => {
+                    self.castling_avail[color.castling_index(true)] = false;
+                    self.castling_avail[color.castling_index(false)] = false;
                 }
-                // TODO
-                return true;
+                7 => self.castling_avail[color.castling_index(true)] = false,
+                0 => self.castling_avail[color.castling_index(false)] = false,
+                _ => {}
             }
-        };
+        }
     }
 
-    pub fn get_consecutive_capture_tiles(&self, start: &Coord, direction: &Coord) -> Vec<Coord> {
-        let mut cursor = start.clone();
-        let mut c = vec![];
-        loop {
-            cursor.offset_in_place(direction);
-            c.push(cursor.clone());
-            if !cursor.is_valid() {
-                break;
+    pub fn get_castling_moves(&self, color: Color) -> Vec<Move> {
+        let rank = color.get_home_rank();
+        let mut moves = vec![];
+        if self.get_tile(&Coord(4, rank)) != Some(Tile(color, Piece::King)) {
+            return moves;
+        }
+        for king_side in &[true, false] {
+            if !self.castling_avail[color.castling_index(*king_side)] {
+                continue;
+            }
+            let (rook_file, empty_files, safe_files): (i8, &[i8], &[i8]) = match king_side {
+                true => (7, &[5, 6], &[4, 5, 6]),
+                false => (0, &[1, 2, 3], &[4, 3, 2]),
+            };
+            if self.get_tile(&Coord(rook_file, rank)) != Some(Tile(color, Piece::Rook)) {
+                continue;
             }
-            if let Some(_) = self.board[cursor.index()] {
-                break;
+            if empty_files
+                .iter()
+                .any(|f| self.get_tile(&Coord(*f, rank)).is_some())
+            {
+                continue;
             }
+            if safe_files
+                .iter()
+                .any(|f| self.is_square_attacked(&Coord(*f, rank), color.opponent()))
+            {
+                continue;
+            }
+            moves.push(Move::Castle(color, *king_side));
         }
-        return c;
+        moves
+    }
+
+    /// Checks whether any piece of color `by` attacks `target`, independent of whose turn it is.
+    pub fn is_square_attacked(&self, target: &Coord, by: Color) -> bool {
+        self.attackers_bitboard(target, by) != 0
+    }
+
+    /// All tiles with a piece of color `by` that attacks the tile.
+    pub fn attackers_of(&self, target: &Coord, by: Color) -> Vec<Coord> {
+        Squares(self.attackers_bitboard(target, by))
+            .map(Coord::from_index)
+            .collect()
+    }
+
+    /// Looks up the attacks of every piece type from the target tile, a piece of that type
+    /// on one of those tiles attacks the target.
+    pub fn attackers_bitboard(&self, target: &Coord, by: Color) -> Bitboard {
+        self.attackers_with_occupancy(target.index(), by, self.get_occupied())
+    }
+
+    /// Like `attackers_bitboard` but sliders are blocked by `occupied` instead of the board.
+    pub fn attackers_with_occupancy(
+        &self,
+        square: usize,
+        by: Color,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        let pieces = |piece| self.get_bitboard(by, piece);
+        let queens = pieces(Piece::Queen);
+        KNIGHT_ATTACKS[square] & pieces(Piece::Knight)
+            | KING_ATTACKS[square] & pieces(Piece::King)
+            // a pawn attacks the tiles that a pawn of the other color would attack from there
+            | PAWN_ATTACKS[by.opponent().index()][square] & pieces(Piece::Pawn)
+            | rook_attacks(square, occupied) & (pieces(Piece::Rook) | queens)
+            | bishop_attacks(square, occupied) & (pieces(Piece::Bishop) | queens)
     }
 
     pub fn state(&self) -> GameState {
-        let check_now = self.is_check();
-        let check_next = self
-            .get_all_possible_moves()
-            .iter()
-            .map(|m| {
-                let mut branch = self.clone();
-                branch.make_move_unchecked(m);
-                branch.is_check()
-            })
-            .any(|a| a.contains(&self.active_color));
-
-        return match (check_now.is_some(), check_next) {
-            (false, true) => GameState::Stalemate,
-            (true, true) => GameState::Checkmate(self.active_color),
-            (true, false) => GameState::Check(check_now.unwrap()),
-            (false, false) => GameState::Normal,
+        let check = self.is_king_attacked(self.active_color);
+        // checkmate and stalemate end the game before any draw rule applies
+        if !self.has_legal_move() {
+            return GameState::Over(if check {
+                Outcome::win(self.active_color.opponent(), Termination::Checkmate)
+            } else {
+                Outcome::draw(Termination::Stalemate)
+            });
+        }
+        if let Some(reason) = self.automatic_draw() {
+            return GameState::Over(Outcome::draw(reason));
+        }
+        let in_check = match check {
+            true => Some(self.active_color),
+            false => None,
         };
+        match (self.claimable_draw(), in_check) {
+            (Some(reason), in_check) => GameState::ClaimableDraw(reason, in_check),
+            (None, Some(color)) => GameState::Check(color),
+            (None, None) => GameState::Normal,
+        }
     }
 
-    pub fn is_check(&self) -> Option<Color> {
-        for col in &[Color::White, Color::Black] {
-            let check = self.get_all_possible_moves_unchecked().iter().any(|m| {
-                match m.get_capture_target() {
-                    None => false,
-                    Some(v) => match self.board[v.index()] {
-                        Some(t) => t == Tile(col.clone(), Piece::King),
-                        None => false,
-                    },
-                }
-            });
-            if check {
-                return Some(col.clone());
-            }
+    pub fn has_legal_move(&self) -> bool {
+        !self.get_all_possible_moves().is_empty()
+    }
+}
+
+impl Outcome {
+    pub fn win(winner: Color, termination: Termination) -> Self {
+        Self {
+            winner: Some(winner),
+            termination,
+        }
+    }
+    pub fn draw(termination: Termination) -> Self {
+        Self {
+            winner: None,
+            termination,
         }
-        None
     }
 }
 
@@ -251,6 +474,21 @@ impl Color {
             Color::White => Coord(0, 1),
         }
     }
+    pub fn get_home_rank(&self) -> i8 {
+        match self {
+            Color::Black => 7,
+            Color::White => 0,
+        }
+    }
+    /// Index into `Game::castling_avail` (KQkq)
+    pub fn castling_index(&self, king_side: bool) -> usize {
+        match (self, king_side) {
+            (Color::White, true) => 0,
+            (Color::White, false) => 1,
+            (Color::Black, true) => 2,
+            (Color::Black, false) => 3,
+        }
+    }
     pub fn opponent(&self) -> Self {
         match self {
             Color::Black => Color::White,
@@ -263,14 +501,21 @@ impl Move {
     pub fn get_source_coord(&self) -> Coord {
         match self {
             Move::Basic(a, _) => a.clone(),
-            Move::Castle(color, side) => match color {
-                Color::White => Coord(4, 0),
-                Color::Black => Coord(4, 7),
-            },
+            Move::Castle(color, _) => Coord(4, color.get_home_rank()),
             Move::EnPassent(a, _) => a.clone(),
             Move::PawnPromotion(a, _, _) => a.clone(),
         }
     }
+    /// The tile the moving piece ends on, for castling the tile of the king.
+    pub fn get_target_coord(&self) -> Coord {
+        match self {
+            Move::Basic(_, a) => a.clone(),
+            Move::Castle(color, true) => Coord(6, color.get_home_rank()),
+            Move::Castle(color, false) => Coord(2, color.get_home_rank()),
+            Move::EnPassent(_, a) => a.clone(),
+            Move::PawnPromotion(_, a, _) => a.clone(),
+        }
+    }
     pub fn get_capture_target(&self) -> Option<Coord> {
         match self {
             Move::Basic(_, a) => Some(a.clone()),
@@ -280,3 +525,545 @@ impl Move {
         }
     }
 }
+
+// Pseudo legal moves that are filtered by trying them on the board. Slower than
+// `generate_legal_moves` but obviously correct, so the tests compare both.
+#[cfg(test)]
+impl Game {
+    pub fn get_all_possible_moves_unchecked(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        for square in Squares(self.colors[self.active_color.index()]) {
+            moves.append(&mut self.get_possible_moves_unchecked(&Coord::from_index(square)))
+        }
+        moves
+    }
+
+    /// Removes all moves that would leave the own king in check.
+    pub fn filter_legal_moves(&mut self, moves: Vec<Move>) -> Vec<Move> {
+        moves
+            .into_iter()
+            .filter(|m| !self.move_results_in_check(m))
+            .collect()
+    }
+
+    pub fn get_possible_moves_unchecked(&self, c: &Coord) -> Vec<Move> {
+        let tile = match self.get_tile(c) {
+            Some(t) => t,
+            None => return vec![],
+        };
+        let mut moves: Vec<Move> = vec![];
+        match tile.1 {
+            Piece::King => moves.append(&mut self.get_castling_moves(tile.0)),
+            Piece::Pawn => {
+                if let Some(m) = self.get_en_passent_move(c, tile.0) {
+                    moves.push(m);
+                }
+            }
+            _ => {}
+        }
+        self.push_moves(c, tile, self.get_targets(c, tile), &mut moves);
+        moves
+    }
+
+    pub fn move_results_in_check(&mut self, m: &Move) -> bool {
+        let color = self.active_color;
+        let undo = self.make_move_unchecked(m);
+        let check = self.is_king_attacked(color);
+        self.unmake_move(m, &undo);
+        check
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::perft::PERFT_POSITIONS;
+    use crate::chess::{Color, Coord, Game, GameState, Move, Outcome, Piece, Termination, Tile};
+
+    fn pawn_moves(fen: &str, tile: &str) -> Vec<String> {
+        let game = Game::from_fen(fen).unwrap();
+        let mut moves = game
+            .get_possible_moves_unchecked(&Coord::from_algebraic(tile).unwrap())
+            .iter()
+            .map(|m| m.serialize())
+            .collect::<Vec<_>>();
+        moves.sort();
+        moves
+    }
+
+    fn move_count(fen: &str, tile: &str) -> usize {
+        let game = Game::from_fen(fen).unwrap();
+        game.get_possible_moves_unchecked(&Coord::from_algebraic(tile).unwrap())
+            .len()
+    }
+
+    #[test]
+    fn sliding_pieces_on_empty_board() {
+        assert_eq!(move_count("4k3/8/8/8/3Q4/8/8/4K3 w - - 0 1", "d4"), 27);
+        assert_eq!(move_count("4k3/8/8/8/3R4/8/8/4K3 w - - 0 1", "d4"), 14);
+        assert_eq!(move_count("4k3/8/8/8/3B4/8/8/4K3 w - - 0 1", "d4"), 13);
+        assert_eq!(move_count("4k3/8/8/8/8/8/8/Q3K3 w - - 0 1", "a1"), 17);
+    }
+
+    #[test]
+    fn sliding_pieces_stop_at_blockers() {
+        // own pieces block, enemy pieces can be captured
+        assert_eq!(
+            move_count("4k3/8/3p4/8/1P1Q1n2/8/3P4/4K3 w - - 0 1", "d4"),
+            19
+        );
+        assert_eq!(
+            move_count("4k3/8/3p4/8/1P1R1n2/8/3P4/4K3 w - - 0 1", "d4"),
+            6
+        );
+        assert_eq!(move_count("4k3/8/5p2/8/3B4/2P5/8/4K3 w - - 0 1", "d4"), 8);
+    }
+
+    #[test]
+    fn all_moves_in_known_positions() {
+        let count = |fen| {
+            Game::from_fen(fen)
+                .unwrap()
+                .get_all_possible_moves_unchecked()
+                .len()
+        };
+        assert_eq!(
+            count("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            20
+        );
+        assert_eq!(
+            count("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+            48
+        );
+    }
+
+    #[test]
+    fn threefold_repetition_is_draw() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/P7/4K1N1 w - - 0 1").unwrap();
+        let shuffle = ["b,g1-f3", "b,e8-d8", "b,f3-g1", "b,d8-e8"];
+        let mut states = vec![];
+        for m in shuffle.iter().chain(shuffle.iter()) {
+            states.push(game.make_move(&Move::deserialize(m).unwrap()).unwrap());
+        }
+        assert_eq!(game.repetition_count(), 3);
+        assert_eq!(
+            states.last(),
+            Some(&GameState::ClaimableDraw(Termination::Repetition, None))
+        );
+        assert!(states[..7].iter().all(|s| *s == GameState::Normal));
+        for m in shuffle.iter().chain(shuffle.iter()) {
+            game.make_move(&Move::deserialize(m).unwrap()).unwrap();
+        }
+        assert_eq!(
+            game.state(),
+            GameState::Over(Outcome::draw(Termination::Repetition))
+        );
+    }
+
+    #[test]
+    fn repetition_ignores_en_passent_target_without_capturing_pawn() {
+        let mut game =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let moves = [
+            "b,e2-e4", "b,g8-f6", "b,g1-f3", "b,f6-g8", "b,f3-g1", "b,g8-f6", "b,g1-f3", "b,f6-g8",
+            "b,f3-g1",
+        ];
+        let mut states = vec![];
+        for m in moves.iter() {
+            states.push(game.make_move(&Move::deserialize(m).unwrap()).unwrap());
+        }
+        // the position after 1.e4 occured after the first, fifth and ninth move
+        assert_eq!(game.repetition_count(), 3);
+        assert_eq!(
+            states.last(),
+            Some(&GameState::ClaimableDraw(Termination::Repetition, None))
+        );
+        assert!(states[..8].iter().all(|s| *s == GameState::Normal));
+
+        // a target that can be captured makes the position different
+        let game = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let without_target = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_ne!(game.get_hash(), without_target.get_hash());
+        let game = Game::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - d6 0 1").unwrap();
+        let without_target = Game::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.get_hash(), without_target.get_hash());
+        // the FEN still has the target
+        assert_eq!(game.to_fen(), "4k3/8/8/3p4/4P3/8/8/4K3 w - d6 0 1");
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K1N1 w - - 98 80").unwrap();
+        game.make_move(&Move::deserialize("b,g1-f3").unwrap())
+            .unwrap();
+        assert_eq!(game.get_halfmove_clock(), 99);
+        assert_eq!(
+            game.make_move(&Move::deserialize("b,e8-d8").unwrap()),
+            Ok(GameState::ClaimableDraw(Termination::FiftyMoveRule, None))
+        );
+        assert_eq!(game.to_fen(), "3k4/8/8/8/8/5N2/4P3/4K3 w - - 100 81");
+
+        // the draw can be claimed while being in check
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert_eq!(
+            game.make_move(&Move::deserialize("b,a1-a8").unwrap()),
+            Ok(GameState::ClaimableDraw(
+                Termination::FiftyMoveRule,
+                Some(Color::Black)
+            ))
+        );
+
+        // pawn moves reset the clock
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K1N1 w - - 98 80").unwrap();
+        game.make_move(&Move::deserialize("b,e2-e4").unwrap())
+            .unwrap();
+        assert_eq!(game.get_halfmove_clock(), 0);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let insufficient = |fen| Game::from_fen(fen).unwrap().is_insufficient_material();
+        assert!(insufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"));
+        assert!(insufficient("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/1B2KB2 w - - 0 1"));
+        assert!(!insufficient("2b1kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/1BB1K3 w - - 0 1"));
+        assert!(!insufficient("4kn2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+        assert_eq!(
+            Game::from_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1")
+                .unwrap()
+                .state(),
+            GameState::Over(Outcome::draw(Termination::InsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn checkmate_positions() {
+        let mates = [
+            // fool's mate
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+            // scholar's mate
+            "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4",
+            // back rank mate
+            "R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1",
+            // smothered mate
+            "6rk/5Npp/8/8/8/8/8/6K1 b - - 0 1",
+        ];
+        for fen in mates.iter() {
+            let game = Game::from_fen(fen).unwrap();
+            assert_eq!(
+                game.state(),
+                GameState::Over(Outcome::win(
+                    game.get_active_color().opponent(),
+                    Termination::Checkmate
+                )),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn stalemate_positions() {
+        let stalemates = [
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            "k7/P7/1K6/8/8/8/8/8 b - - 0 1",
+            "5k2/5P2/5K2/8/8/8/8/8 b - - 0 1",
+            // the only other piece is pinned
+            "k7/1r1N4/1P6/8/4Q3/8/8/6K1 b - - 0 1",
+        ];
+        for fen in stalemates.iter() {
+            assert_eq!(
+                Game::from_fen(fen).unwrap().state(),
+                GameState::Over(Outcome::draw(Termination::Stalemate)),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn check_and_normal_positions() {
+        let state = |fen| Game::from_fen(fen).unwrap().state();
+        assert_eq!(
+            state("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            GameState::Normal
+        );
+        // every move of the pinned bishop would be illegal but the king can still move
+        assert_eq!(
+            state("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1"),
+            GameState::Normal
+        );
+        assert_eq!(
+            state("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1"),
+            GameState::Check(Color::Black)
+        );
+        // the check can be blocked
+        assert_eq!(
+            state("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P1N/PPPPP2P/RNBQKB1R w KQkq - 1 3"),
+            GameState::Check(Color::White)
+        );
+    }
+
+    #[test]
+    fn attackers_of_tile() {
+        let game = Game::from_fen("4k3/8/3r4/2n5/4P3/8/4Q3/R3K3 w - - 0 1").unwrap();
+        let attackers = |tile, color| {
+            let mut tiles = game
+                .attackers_of(&Coord::from_algebraic(tile).unwrap(), color)
+                .iter()
+                .map(|c| c.to_algebraic())
+                .collect::<Vec<_>>();
+            tiles.sort();
+            tiles
+        };
+        assert_eq!(attackers("d4", Color::Black), vec!["d6"]);
+        assert_eq!(attackers("d3", Color::White), vec!["e2"]);
+        assert_eq!(attackers("d5", Color::White), vec!["e4"]);
+        assert_eq!(attackers("d3", Color::Black), vec!["c5", "d6"]);
+        // the queen is blocked by the pawn
+        assert_eq!(attackers("e5", Color::White), Vec::<String>::new());
+        assert_eq!(attackers("a6", Color::White), vec!["a1", "e2"]);
+        assert!(game.is_square_attacked(&Coord::from_algebraic("e4").unwrap(), Color::Black));
+        assert!(!game.is_square_attacked(&Coord::from_algebraic("h1").unwrap(), Color::Black));
+    }
+
+    #[test]
+    fn unmake_move_restores_position() {
+        for (fen, _) in PERFT_POSITIONS {
+            let mut game = Game::from_fen(fen).unwrap();
+            let original = game.clone();
+            for m in game.get_all_possible_moves_unchecked() {
+                let undo = game.make_move_unchecked(&m);
+                for reply in game.get_all_possible_moves_unchecked() {
+                    let reply_undo = game.make_move_unchecked(&reply);
+                    game.unmake_move(&reply, &reply_undo);
+                }
+                game.unmake_move(&m, &undo);
+                assert_eq!(game, original, "{} in {}", m, fen);
+            }
+        }
+    }
+
+    fn play(fen: &str, moves: &[&str]) -> Game {
+        let mut game = Game::from_fen(fen).unwrap();
+        for m in moves {
+            game.make_move(&Move::deserialize(m).unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn castling_rights_lost_after_king_and_rook_moves() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        // `dump` prints the FEN, so the castling field shows the remaining rights
+        let rights = |moves: &[&str]| {
+            play(fen, moves)
+                .to_fen()
+                .split(' ')
+                .nth(2)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(rights(&["b,h1-h2"]), "Qkq");
+        assert_eq!(rights(&["b,h1-h2", "b,a8-a7"]), "Qk");
+        assert_eq!(rights(&["b,h1-h2", "b,a8-a7", "b,e1-e2"]), "k");
+        // moving back does not bring the rights back
+        assert_eq!(rights(&["b,h1-h2", "b,a8-a7", "b,h2-h1", "b,a7-a8"]), "Qk");
+        assert_eq!(rights(&["b,e1-d1", "b,e8-d8"]), "-");
+        let game = play(fen, &["b,h1-h2", "b,a8-a7"]);
+        assert_eq!(
+            game.get_castling_moves(Color::White),
+            vec![Move::Castle(Color::White, false)]
+        );
+        assert_eq!(
+            game.get_castling_moves(Color::Black),
+            vec![Move::Castle(Color::Black, true)]
+        );
+    }
+
+    #[test]
+    fn castling_rights_lost_when_rook_is_captured() {
+        let game = play("r3k2r/1B6/8/8/8/8/8/R3K2R w KQkq - 0 1", &["b,b7-a8"]);
+        assert_eq!(game.to_fen(), "B3k2r/8/8/8/8/8/8/R3K2R b KQk - 0 1");
+        assert_eq!(
+            game.get_castling_moves(Color::Black),
+            vec![Move::Castle(Color::Black, true)]
+        );
+    }
+
+    #[test]
+    fn castling_through_attacked_tiles() {
+        // f1 is attacked, b1 may be attacked because only the rook passes it
+        let game = Game::from_fen("r3k2r/8/8/8/8/1r6/5r2/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(
+            game.get_castling_moves(Color::White),
+            vec![Move::Castle(Color::White, false)]
+        );
+        // no castling out of check
+        let game = Game::from_fen("r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1").unwrap();
+        assert!(game.get_castling_moves(Color::White).is_empty());
+        assert!(game
+            .get_all_possible_moves()
+            .iter()
+            .all(|m| !matches!(m, Move::Castle(..))));
+    }
+
+    #[test]
+    fn castling_moves_king_and_rook() {
+        let game = play("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &["r,K", "r,q"]);
+        assert_eq!(game.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+    }
+
+    #[test]
+    fn en_passent_target_after_double_push() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(
+            play(start, &["b,e2-e4"]).to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        // the target is only valid for the next move
+        assert_eq!(
+            play(start, &["b,e2-e4", "b,g8-f6"]).to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+        );
+        assert_eq!(
+            play(start, &["b,e2-e4", "b,d7-d5"]).to_fen(),
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"
+        );
+        // single pushes don't set a target
+        assert_eq!(
+            play(start, &["b,e2-e3"]).to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/4P3/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn promotions_on_push_and_capture() {
+        assert_eq!(
+            pawn_moves("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7"),
+            vec![
+                "p,a7-a8,B",
+                "p,a7-a8,N",
+                "p,a7-a8,Q",
+                "p,a7-a8,R",
+                "p,a7-b8,B",
+                "p,a7-b8,N",
+                "p,a7-b8,Q",
+                "p,a7-b8,R"
+            ]
+        );
+        assert_eq!(
+            pawn_moves("4k3/8/8/8/8/8/p7/4K3 b - - 0 1", "a2"),
+            vec!["p,a2-a1,b", "p,a2-a1,n", "p,a2-a1,q", "p,a2-a1,r"]
+        );
+        let mut game = play("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", &["p,a7-a8,N"]);
+        assert_eq!(game.to_fen(), "N3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        game.make_move(&Move::deserialize("b,e8-d8").unwrap())
+            .unwrap();
+        assert_eq!(game.get_possible_moves(&Coord(0, 7)).len(), 2);
+    }
+
+    #[test]
+    fn promotion_to_king_or_pawn_is_rejected() {
+        let mut game = Game::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        for m in &["p,a7-a8,K", "p,a7-a8,P"] {
+            assert!(game.make_move(&Move::deserialize(m).unwrap()).is_err());
+        }
+        assert_eq!(game.to_fen(), "4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    }
+
+    #[test]
+    fn en_passent_only_captures_a_pawn() {
+        let mut game = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let e5 = Coord::from_algebraic("e5").unwrap();
+        assert_eq!(
+            game.get_en_passent_move(&e5, Color::White),
+            Some(Move::deserialize("e,e5-d6").unwrap())
+        );
+        game.set_tile(
+            &Coord::from_algebraic("d5").unwrap(),
+            Some(Tile(Color::Black, Piece::Knight)),
+        );
+        assert_eq!(game.get_en_passent_move(&e5, Color::White), None);
+        game.set_tile(
+            &Coord::from_algebraic("d5").unwrap(),
+            Some(Tile(Color::Black, Piece::Pawn)),
+        );
+        game.set_tile(
+            &Coord::from_algebraic("d6").unwrap(),
+            Some(Tile(Color::Black, Piece::Knight)),
+        );
+        assert_eq!(game.get_en_passent_move(&e5, Color::White), None);
+    }
+
+    #[test]
+    fn pawn_single_and_double_push() {
+        assert_eq!(
+            pawn_moves("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2"),
+            vec!["b,e2-e3", "b,e2-e4"]
+        );
+        assert_eq!(
+            pawn_moves("4k3/4p3/8/8/8/8/8/4K3 b - - 0 1", "e7"),
+            vec!["b,e7-e5", "b,e7-e6"]
+        );
+    }
+
+    #[test]
+    fn pawn_double_push_only_from_start_rank() {
+        assert_eq!(
+            pawn_moves("4k3/8/8/8/8/4P3/8/4K3 w - - 0 1", "e3"),
+            vec!["b,e3-e4"]
+        );
+        assert_eq!(
+            pawn_moves("4k3/8/4p3/8/8/8/8/4K3 b - - 0 1", "e6"),
+            vec!["b,e6-e5"]
+        );
+    }
+
+    #[test]
+    fn pawn_push_blocked() {
+        assert!(pawn_moves("4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1", "e2").is_empty());
+        assert!(pawn_moves("4k3/8/8/8/8/4N3/4P3/4K3 w - - 0 1", "e2").is_empty());
+        assert!(pawn_moves("4k3/4p3/4N3/8/8/8/8/4K3 b - - 0 1", "e7").is_empty());
+    }
+
+    #[test]
+    fn pawn_double_push_blocked() {
+        assert_eq!(
+            pawn_moves("4k3/8/8/8/4n3/8/4P3/4K3 w - - 0 1", "e2"),
+            vec!["b,e2-e3"]
+        );
+        assert_eq!(
+            pawn_moves("4k3/4p3/8/4N3/8/8/8/4K3 b - - 0 1", "e7"),
+            vec!["b,e7-e6"]
+        );
+    }
+
+    #[test]
+    fn pawn_diagonal_captures() {
+        assert_eq!(
+            pawn_moves("4k3/8/8/8/8/3n1b2/4P3/4K3 w - - 0 1", "e2"),
+            vec!["b,e2-d3", "b,e2-e3", "b,e2-e4", "b,e2-f3"]
+        );
+        assert_eq!(
+            pawn_moves("4k3/4p3/3N1B2/8/8/8/8/4K3 b - - 0 1", "e7"),
+            vec!["b,e7-d6", "b,e7-e5", "b,e7-e6", "b,e7-f6"]
+        );
+    }
+
+    #[test]
+    fn pawn_does_not_capture_own_pieces_or_empty_diagonals() {
+        assert_eq!(
+            pawn_moves("4k3/8/8/8/8/3N1B2/4P3/4K3 w - - 0 1", "e2"),
+            vec!["b,e2-e3", "b,e2-e4"]
+        );
+        assert_eq!(
+            pawn_moves("4k3/8/8/8/8/8/P7/4K3 w - - 0 1", "a2"),
+            vec!["b,a2-a3", "b,a2-a4"]
+        );
+    }
+}
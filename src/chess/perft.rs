@@ -0,0 +1,137 @@
+use super::{Game, Move};
+
+impl Game {
+    /// Counts the leaf nodes of the legal move tree with the given depth.
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        // one move buffer per depth, reused by all nodes of that depth
+        let mut buffers = vec![vec![]; depth];
+        self.perft_with(&mut buffers)
+    }
+
+    fn perft_with(&mut self, buffers: &mut [Vec<Move>]) -> u64 {
+        let (moves, rest) = match buffers.split_first_mut() {
+            Some(split) => split,
+            None => return 1,
+        };
+        moves.clear();
+        self.push_legal_moves(self.colors[self.active_color.index()], moves);
+        if rest.is_empty() {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for m in moves.iter() {
+            let undo = self.make_move_unchecked(m);
+            nodes += self.perft_with(rest);
+            self.unmake_move(m, &undo);
+        }
+        nodes
+    }
+
+    /// Like `perft` but split up by the first move.
+    pub fn divide(&mut self, depth: usize) -> Vec<(Move, u64)> {
+        let mut counts = vec![];
+        for m in self.get_all_possible_moves() {
+            let undo = self.make_move_unchecked(&m);
+            let count = self.perft(depth.saturating_sub(1));
+            self.unmake_move(&m, &undo);
+            counts.push((m, count));
+        }
+        counts
+    }
+}
+
+/// Positions with known perft results, see https://www.chessprogramming.org/Perft_Results
+pub const PERFT_POSITIONS: &[(&str, &[u64])] = &[
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8902, 197281],
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862],
+    ),
+    (
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2812, 43238],
+    ),
+    (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467],
+    ),
+    (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62379],
+    ),
+    (
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89890],
+    ),
+    // edge cases of en passent and check evasion
+    ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", &[18, 92, 1670, 10138]),
+    (
+        "8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1",
+        &[13, 102, 1266, 10276],
+    ),
+    (
+        "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        &[15, 126, 1928, 13931],
+    ),
+    ("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", &[37, 183, 6559, 23527]),
+    (
+        "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+        &[29, 165, 5160, 31961],
+    ),
+    // en passent would expose the king along the rank
+    ("8/8/8/K1Pp3r/8/8/8/7k w - d6 0 1", &[6, 78, 494, 7836]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::PERFT_POSITIONS;
+    use crate::chess::Game;
+
+    fn assert_perft(position: usize) {
+        let (fen, expected) = PERFT_POSITIONS[position];
+        let mut game = Game::from_fen(fen).unwrap();
+        for (i, count) in expected.iter().enumerate() {
+            assert_eq!(game.perft(i + 1), *count, "depth {} of {}", i + 1, fen);
+        }
+    }
+
+    #[test]
+    fn perft_start_position() {
+        assert_perft(0);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(1);
+    }
+
+    #[test]
+    fn perft_position_3() {
+        assert_perft(2);
+    }
+
+    #[test]
+    fn perft_position_4() {
+        assert_perft(3);
+    }
+
+    #[test]
+    fn perft_position_5() {
+        assert_perft(4);
+    }
+
+    #[test]
+    fn perft_position_6() {
+        assert_perft(5);
+    }
+
+    #[test]
+    fn perft_edge_cases() {
+        for position in 6..PERFT_POSITIONS.len() {
+            assert_perft(position);
+        }
+    }
+}
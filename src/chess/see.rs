@@ -0,0 +1,124 @@
+use super::bitboard::Squares;
+use super::{Coord, Game, Move, Piece};
+
+/// Value of the king in an exchange, more than everything else together because it never
+/// gets captured.
+const KING_VALUE: i32 = 10_000;
+
+fn value(piece: Piece) -> i32 {
+    match piece {
+        Piece::King => KING_VALUE,
+        _ => piece.value(),
+    }
+}
+
+/// Cheapest first, the order pieces join an exchange.
+const EXCHANGE_ORDER: [Piece; 6] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
+
+impl Game {
+    /// Static exchange evaluation: the material the active color wins with the move if both
+    /// sides keep recapturing on the target tile with their least valuable piece, and either
+    /// side stops as soon as going on would lose material. Sliders standing behind a piece
+    /// that takes part join the exchange once it left.
+    pub fn see(&self, m: &Move) -> i32 {
+        let from = m.get_source_coord();
+        let to = m.get_target_coord().index();
+        let mut occupied = self.get_occupied() & !(1 << from.index());
+        let (mut on_target, mut gain) = match m {
+            Move::Castle(_, _) => return 0,
+            Move::PawnPromotion(_, _, piece) => (*piece, value(*piece) - value(Piece::Pawn)),
+            _ => match self.get_tile(&from) {
+                Some(tile) => (tile.1, 0),
+                None => return 0,
+            },
+        };
+        if let Move::EnPassent(_, target) = m {
+            occupied &= !(1 << Coord(target.0, from.1).index());
+        }
+        gain += self.get_captured_piece(m).map_or(0, value);
+
+        // gains[i] is the material won by the side making the i-th capture if it is the last one
+        let mut gains = vec![gain];
+        let mut side = self.active_color.opponent();
+        loop {
+            let attackers = self.attackers_with_occupancy(to, side, occupied) & occupied;
+            let cheapest = EXCHANGE_ORDER.iter().find_map(|piece| {
+                Squares(attackers & self.pieces[piece.index()])
+                    .next()
+                    .map(|square| (square, *piece))
+            });
+            let (square, piece) = match cheapest {
+                Some(attacker) => attacker,
+                None => break,
+            };
+            // the king can only capture if the tile is no longer defended
+            if piece == Piece::King
+                && self.attackers_with_occupancy(to, side.opponent(), occupied) & occupied != 0
+            {
+                break;
+            }
+            gains.push(value(on_target) - gains[gains.len() - 1]);
+            on_target = piece;
+            occupied &= !(1 << square);
+            side = side.opponent();
+        }
+        // going backwards every side either makes its capture or stops before it
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.last_mut().unwrap();
+            *previous = -(-*previous).max(last);
+        }
+        gains[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::{Game, Move};
+
+    fn see(fen: &str, m: &str) -> i32 {
+        Game::from_fen(fen)
+            .unwrap()
+            .see(&Move::deserialize(m).unwrap())
+    }
+
+    #[test]
+    fn see_of_captures() {
+        // undefended pawn
+        assert_eq!(
+            see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "b,e1-e5"),
+            100
+        );
+        // the knight is lost after the pawn is taken, recapturing would lose even more
+        assert_eq!(
+            see(
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "b,d3-e5"
+            ),
+            -220
+        );
+        // the second rook joins through the first one
+        assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "b,d2-d5"), 100);
+        assert_eq!(see("3rk3/8/8/3p4/8/8/8/3RK3 w - - 0 1", "b,d1-d5"), -400);
+        // the king recaptures the queen
+        assert_eq!(see("4k3/3q4/8/8/8/8/3R4/4K3 b - - 0 1", "b,d7-d2"), -400);
+        // the king can not recapture because of the rook behind the queen
+        assert_eq!(see("3rk3/3q4/8/8/8/8/3R4/4K3 b - - 0 1", "b,d7-d2"), 500);
+    }
+
+    #[test]
+    fn see_of_special_moves() {
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e,e5-d6"), 100);
+        // the promoted queen is captured right away
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "p,b7-b8,Q"), -100);
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "p,b7-a8,Q"), 1300);
+        assert_eq!(see("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", "r,Q"), 0);
+    }
+}
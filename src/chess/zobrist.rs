@@ -0,0 +1,121 @@
+use super::bitboard::{Squares, PAWN_ATTACKS};
+use super::{Color, Coord, Game, Piece, Tile};
+
+// Random keys that are xor'ed together for every feature of a position:
+// 2 * 6 * 64 for pieces, 1 for black to move, 4 for castling rights, 8 for en passent files
+const KEY_COUNT: usize = 781;
+const SIDE_KEY: usize = 768;
+const CASTLING_KEYS: usize = 769;
+const EN_PASSENT_KEYS: usize = 773;
+
+const KEYS: [u64; KEY_COUNT] = keys();
+
+/// splitmix64 with a fixed seed so that hashes are reproducible
+const fn keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x6b65_6b63_6865_7373;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+pub fn tile_key(tile: Tile, square: usize) -> u64 {
+    KEYS[(tile.0.index() * 6 + tile.1.index()) * 64 + square]
+}
+
+pub fn side_key() -> u64 {
+    KEYS[SIDE_KEY]
+}
+
+pub fn castling_key(castling_avail: &[bool; 4]) -> u64 {
+    (0..4)
+        .filter(|i| castling_avail[*i])
+        .fold(0, |key, i| key ^ KEYS[CASTLING_KEYS + i])
+}
+
+pub fn en_passent_key(target: &Coord) -> u64 {
+    KEYS[EN_PASSENT_KEYS + target.file_index()]
+}
+
+impl Game {
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Key of the en passent target. It only counts if a pawn of the active color could
+    /// capture there, otherwise the position is the same as without the target and has to
+    /// hash the same for repetitions.
+    pub fn en_passent_hash(&self) -> u64 {
+        match &self.en_passent_target {
+            Some(target)
+                if PAWN_ATTACKS[self.active_color.opponent().index()][target.index()]
+                    & self.get_bitboard(self.active_color, Piece::Pawn)
+                    != 0 =>
+            {
+                en_passent_key(target)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Computes the hash of the position from scratch, `get_hash` returns the same but is
+    /// updated with every move.
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = castling_key(&self.castling_avail) ^ self.en_passent_hash();
+        if self.active_color == Color::Black {
+            hash ^= side_key();
+        }
+        for color in &[Color::White, Color::Black] {
+            for piece in &Piece::ALL {
+                for square in Squares(self.get_bitboard(*color, *piece)) {
+                    hash ^= tile_key(Tile(*color, *piece), square);
+                }
+            }
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::chess::perft::PERFT_POSITIONS;
+    use crate::chess::Game;
+
+    #[test]
+    fn incremental_hash_matches_computed_hash() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for (fen, _) in PERFT_POSITIONS {
+            for _ in 0..10 {
+                let mut game = Game::from_fen(fen).unwrap();
+                let original = game.get_hash();
+                assert_eq!(original, game.compute_hash());
+                let mut history = vec![];
+                for _ in 0..40 {
+                    let moves = game.get_all_possible_moves();
+                    if moves.is_empty() {
+                        break;
+                    }
+                    let m = moves[rng.gen_range(0..moves.len())].clone();
+                    let undo = game.make_move_unchecked(&m);
+                    assert_eq!(game.get_hash(), game.compute_hash(), "after {}", m);
+                    history.push((m, undo));
+                }
+                for (m, undo) in history.iter().rev() {
+                    game.unmake_move(m, undo);
+                    assert_eq!(game.get_hash(), game.compute_hash(), "after undoing {}", m);
+                }
+                assert_eq!(game.get_hash(), original);
+            }
+        }
+    }
+}
@@ -1,12 +1,22 @@
 use std::io::{BufRead, Read};
+use std::time::Instant;
 
-use algos::algo_move;
+use algos::limits::SearchLimits;
+use algos::minimax::SearchInfo;
+use algos::tt::{self, TranspositionTable};
+use algos::{algo_move, eval, minimax};
 
 use crate::algos;
-use crate::chess::{Coord, Game, GameState, Move};
+use crate::chess::bitboard;
+use crate::chess::perft::PERFT_POSITIONS;
+use crate::chess::{Color, Coord, Game, GameState, Move};
+
+const BENCH_SEARCH_DEPTH: usize = 5;
 
 pub struct InteractiveMode {
     game: Game,
+    tt: TranspositionTable,
+    multipv: usize,
 }
 
 impl InteractiveMode {
@@ -14,6 +24,26 @@ impl InteractiveMode {
         Self {
             game: Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
                 .unwrap(),
+            tt: TranspositionTable::new(tt::DEFAULT_SIZE_MB).unwrap(),
+            multipv: 1,
+        }
+    }
+
+    pub fn log_search_info(info: &SearchInfo) {
+        for (i, line) in info.lines.iter().enumerate() {
+            println!(
+                "INFO depth {} multipv {} score {} nodes {} time {} pv {}",
+                info.depth,
+                i + 1,
+                minimax::format_score(line.score),
+                info.nodes,
+                info.time.as_millis(),
+                line.moves
+                    .iter()
+                    .map(|m| m.serialize())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
         }
     }
 
@@ -22,14 +52,14 @@ impl InteractiveMode {
             GameState::Check(c) => {
                 println!("INFO {} is in check", c)
             }
-            GameState::Checkmate(c) => {
-                println!("INFO game ended. checkmate for {}", c)
-            }
-            GameState::Stalemate => {
-                println!("INFO game ended. stalemate")
+            GameState::Over(outcome) => {
+                println!("INFO game ended. {} {}", outcome.as_pgn_result(), outcome)
             }
-            GameState::Draw => {
-                println!("INFO game ended. draw.")
+            GameState::ClaimableDraw(reason, check) => {
+                if let Some(c) = check {
+                    println!("INFO {} is in check", c)
+                }
+                println!("INFO draw by {} can be claimed", reason)
             }
             GameState::Normal => {}
         }
@@ -52,7 +82,11 @@ impl InteractiveMode {
                     println!("OK {}", self.game.to_fen())
                 }
                 "dump_debug" => {
-                    println!("{}\nhttps://lichess.org/editor/{}\nOK",self.game,self.game.to_fen().replace(" ", "_"))
+                    println!(
+                        "{}\nhttps://lichess.org/editor/{}\nOK",
+                        self.game,
+                        self.game.to_fen().replace(" ", "_")
+                    )
                 }
                 "possible_moves" => {
                     if spl.len() != 2 {
@@ -70,6 +104,27 @@ impl InteractiveMode {
                         }
                     }
                 }
+                "attackers" => {
+                    if spl.len() != 2 {
+                        println!("ERROR: argument count is incorrect")
+                    } else {
+                        match Coord::from_algebraic(spl[1]) {
+                            Err(msg) => println!("ERROR: {}", msg),
+                            Ok(c) => {
+                                let mut attackers = self.game.attackers_of(&c, Color::White);
+                                attackers.append(&mut self.game.attackers_of(&c, Color::Black));
+                                println!(
+                                    "OK {}",
+                                    attackers
+                                        .iter()
+                                        .map(|a| a.to_algebraic())
+                                        .collect::<Vec<_>>()
+                                        .join(" ")
+                                );
+                            }
+                        }
+                    }
+                }
                 "all_possible_moves" => {
                     let moves = self.game.get_all_possible_moves();
                     println!("OK");
@@ -77,26 +132,132 @@ impl InteractiveMode {
                         println!("{}", m.serialize());
                     }
                 }
-                "move" => {
+                "eval" => {
+                    println!("OK {}", minimax::format_score(eval::evaluate(&self.game)))
+                }
+                "perft" | "divide" => {
                     if spl.len() != 2 {
                         println!("ERROR: argument count is incorrect")
                     } else {
-                        let m = Move::deserialize(spl[1]);
-                        match self.game.make_move(&m) {
-                            Err(msg) => {
-                                println!("WARN while applying move: {}", msg)
+                        match spl[1].parse::<usize>() {
+                            Err(_) => println!("ERROR: depth is not a number: {:?}", spl[1]),
+                            Ok(depth) if spl[0] == "perft" => {
+                                println!("OK {}", self.game.perft(depth))
+                            }
+                            Ok(depth) => {
+                                let counts = self.game.divide(depth);
+                                println!("OK {}", counts.iter().map(|(_, n)| n).sum::<u64>());
+                                for (m, n) in counts {
+                                    println!("{} {}", m.serialize(), n);
+                                }
                             }
-                            Ok(state) => self.log_state(state),
+                        }
+                    }
+                }
+                "bench" => {
+                    // perft of all known positions up to their deepest known depth
+                    let start = Instant::now();
+                    let mut nodes = 0;
+                    for (fen, counts) in PERFT_POSITIONS {
+                        let mut game = Game::from_fen(fen).unwrap();
+                        let position_start = Instant::now();
+                        let n = game.perft(counts.len());
+                        println!(
+                            "INFO {} nodes in {}ms: {}",
+                            n,
+                            position_start.elapsed().as_millis(),
+                            fen
+                        );
+                        nodes += n;
+                    }
+                    let elapsed = start.elapsed();
+                    println!("INFO sliding attacks from {}", bitboard::SLIDING_ATTACKS);
+                    println!(
+                        "OK {} nodes in {}ms ({} nodes/s)",
+                        nodes,
+                        elapsed.as_millis(),
+                        (nodes as f64 / elapsed.as_secs_f64()) as u64
+                    );
+                }
+                "bench_search" => {
+                    // fixed depth searches of all known positions, fewer nodes mean better move ordering
+                    let depth = match spl.get(1).map(|d| d.parse::<usize>()) {
+                        None => Ok(BENCH_SEARCH_DEPTH),
+                        Some(d) => d,
+                    };
+                    match depth {
+                        Err(e) => println!("ERROR: depth is not a number: {}", e),
+                        Ok(depth) => {
+                            let start = Instant::now();
+                            let mut nodes = 0;
+                            for (fen, _) in PERFT_POSITIONS {
+                                let game = Game::from_fen(fen).unwrap();
+                                let position_start = Instant::now();
+                                let n = minimax::count_nodes(&game, depth);
+                                println!(
+                                    "INFO {} nodes in {}ms: {}",
+                                    n,
+                                    position_start.elapsed().as_millis(),
+                                    fen
+                                );
+                                nodes += n;
+                            }
+                            let elapsed = start.elapsed();
+                            println!(
+                                "OK {} nodes in {}ms ({} nodes/s)",
+                                nodes,
+                                elapsed.as_millis(),
+                                (nodes as f64 / elapsed.as_secs_f64()) as u64
+                            );
+                        }
+                    }
+                }
+                "move" => {
+                    if spl.len() != 2 {
+                        println!("ERROR: argument count is incorrect")
+                    } else {
+                        match Move::deserialize(spl[1]) {
+                            Err(msg) => println!("ERROR: {}", msg),
+                            Ok(m) => match self.game.make_move(&m) {
+                                Err(msg) => {
+                                    println!("WARN while applying move: {}", msg)
+                                }
+                                Ok(state) => {
+                                    self.log_state(state);
+                                    println!("OK")
+                                }
+                            },
                         }
                     }
                 }
                 "algo" => {
-                    if spl.len() != 3 {
+                    // a single number is the depth, otherwise pairs of limit names and values
+                    let limits = match spl.get(3..) {
+                        Some(&[depth]) => depth
+                            .parse::<usize>()
+                            .map(SearchLimits::depth)
+                            .map_err(|e| format!("depth is not a number: {}", e)),
+                        Some(args) => SearchLimits::parse(args),
+                        None => Ok(SearchLimits::default()),
+                    };
+                    if spl.len() < 3 {
                         println!("ERROR: argument count is incorrect")
+                    } else if let Err(msg) = &limits {
+                        println!("ERROR: {}", msg)
                     } else {
-                        match algo_move(spl[1], &self.game) {
+                        match algo_move(
+                            spl[1],
+                            &self.game,
+                            &limits.unwrap(),
+                            self.multipv,
+                            &mut self.tt,
+                            &mut Self::log_search_info,
+                        ) {
                             Err(msg) => println!("ERROR: {}", msg),
-                            Ok(m) => {
+                            Ok((m, score)) => {
+                                if let Some(score) = score {
+                                    println!("INFO score {}", minimax::format_score(score))
+                                }
                                 match spl[2] {
                                     "false" => {}
                                     "true" => match self.game.make_move(&m) {
@@ -109,11 +270,29 @@ impl InteractiveMode {
                                         println!("WARN 'do move' argument invalid")
                                     }
                                 }
-                                println!("OK {}", m)
+                                println!("OK {}", m.serialize())
                             }
                         }
                     }
                 }
+                "setoption" => match (spl.get(1), spl.get(2).map(|v| v.parse::<usize>())) {
+                    (Some(&"hash"), Some(Ok(megabytes))) if spl.len() == 3 => {
+                        match TranspositionTable::new(megabytes) {
+                            Err(msg) => println!("ERROR: {}", msg),
+                            Ok(tt) => {
+                                self.tt = tt;
+                                println!("OK")
+                            }
+                        }
+                    }
+                    (Some(&"hash"), _) => println!("ERROR: usage: setoption hash <megabytes>"),
+                    (Some(&"multipv"), Some(Ok(lines))) if spl.len() == 3 && lines > 0 => {
+                        self.multipv = lines;
+                        println!("OK")
+                    }
+                    (Some(&"multipv"), _) => println!("ERROR: usage: setoption multipv <lines>"),
+                    _ => println!("ERROR: unknown option"),
+                },
                 "quit" => {
                     break;
                 }
@@ -1,5 +1,3 @@
-#![feature(exclusive_range_pattern)]
-#![feature(option_result_contains)]
 #![allow(dead_code, unused_variables, unused_imports)]
 
 mod algos;